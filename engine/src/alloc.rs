@@ -0,0 +1,106 @@
+//! Genesis `alloc` state dumps - i.e. as per geth's `genesis.json` and `evm t8n --input.alloc`
+//!
+//! An [`Alloc`] maps each address to its balance, nonce, code and storage, so state can be seeded
+//! (see [`Engine::load_alloc`](crate::Engine::load_alloc)) and dumped (see
+//! [`Engine::dump_alloc`](crate::Engine::dump_alloc)) as fixtures shared with geth and other tools.
+//!
+//! # Example (as serialized JSON)
+//!
+//! ```json
+//! {
+//!   "0x00000000000000000000000000000000000000aa": {
+//!     "balance": "0xde0b6b3a7640000",
+//!     "nonce": "0x1",
+//!     "code": "0x5f5460015500",
+//!     "storage": {
+//!       "0x0000000000000000000000000000000000000000000000000000000000000000": "0x0000000000000000000000000000000000000000000000000000000000000001"
+//!     }
+//!   }
+//! }
+//! ```
+
+use revm::{
+    bytecode::Bytecode,
+    primitives::{Address, B256, Bytes, U256, alloy_primitives::U64},
+    state::{Account, AccountInfo, EvmStorageSlot},
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// State dump mapping each address to its account (ordered by address)
+pub type Alloc = BTreeMap<Address, GenesisAccount>;
+
+/// An account of an [`Alloc`]
+///
+/// NOTE: as per geth, the nonce is encoded as a hex quantity and storage as 32-byte words,
+/// although shorter (i.e. unpadded) storage keys and values are accepted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisAccount {
+    /// Balance of the account (in wei)
+    #[serde(default)]
+    pub balance: U256,
+    /// Nonce of the account
+    #[serde(default, with = "quantity", skip_serializing_if = "is_zero")]
+    pub nonce: u64,
+    /// Code of the account (empty for an externally owned account)
+    #[serde(default, skip_serializing_if = "is_empty")]
+    pub code: Bytes,
+    /// Storage of the account (zero-valued slots are omitted)
+    #[serde(
+        default,
+        serialize_with = "storage",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl From<GenesisAccount> for Account {
+    fn from(account: GenesisAccount) -> Self {
+        let info = AccountInfo::from_balance(account.balance).with_nonce(account.nonce);
+        let info = match account.code.is_empty() {
+            true => info,
+            // NOTE: invalid EOF code is treated as legacy code (i.e. rather than panicking)
+            false => info.with_code(
+                Bytecode::new_raw_checked(account.code.clone())
+                    .unwrap_or_else(|_| Bytecode::new_legacy(account.code)),
+            ),
+        };
+        Account::from(info).with_storage(
+            account
+                .storage
+                .into_iter()
+                .map(|(slot, value)| (slot, EvmStorageSlot::new(value))),
+        )
+    }
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+fn is_empty(code: &Bytes) -> bool {
+    code.is_empty()
+}
+
+pub(crate) mod quantity {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        U64::from(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        Ok(U64::deserialize(deserializer)?.to())
+    }
+}
+
+fn storage<S: Serializer>(
+    storage: &BTreeMap<U256, U256>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        storage
+            .iter()
+            .map(|(slot, value)| (B256::from(*slot), B256::from(*value))),
+    )
+}
@@ -0,0 +1,53 @@
+//! Runs the Ethereum `GeneralStateTests` fixtures at the given paths (i.e. files or directories),
+//! reporting whether each test passed, failed or was skipped (i.e. its fork is not supported)
+//!
+//! ```sh
+//! cargo run --release --bin statetest -- path/to/GeneralStateTests
+//! ```
+
+use engine::statetest;
+use std::{env, process::ExitCode};
+
+fn main() -> ExitCode {
+    let paths: Vec<_> = env::args_os().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: statetest <path>...");
+        return ExitCode::FAILURE;
+    }
+
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    for path in paths {
+        let fixtures = match statetest::find(path.as_ref()) {
+            Ok(fixtures) => fixtures,
+            Err(err) => {
+                eprintln!("{}: {err}", path.to_string_lossy());
+                return ExitCode::FAILURE;
+            }
+        };
+
+        for fixture in fixtures {
+            match statetest::run(&fixture) {
+                Ok(outcomes) => {
+                    for outcome in outcomes {
+                        println!("{outcome}");
+                        match outcome.result {
+                            None => skipped += 1,
+                            Some(Ok(())) => passed += 1,
+                            Some(Err(_)) => failed += 1,
+                        }
+                    }
+                }
+                Err(err) => {
+                    println!("FAIL {}: {err}", fixture.display());
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    println!("{passed} passed, {failed} failed, {skipped} skipped");
+    match failed {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}
@@ -0,0 +1,407 @@
+//! Block-level execution - i.e. the transactions of a block, followed by the block reward and
+//! withdrawals, producing receipts and the resulting state root
+//!
+//! # Example
+//!
+//! ```
+//! # use engine::{Engine, block::{Block, Withdrawal}};
+//! # use revm::primitives::address;
+//! let mut engine = Engine::new();
+//! let executed = engine
+//!     .execute_block(Block {
+//!         withdrawals: vec![Withdrawal {
+//!             address: address!("00000000000000000000000000000000000000cc"),
+//!             amount: 1_000_000_000, // 1 ETH (in gwei)
+//!             ..Default::default()
+//!         }],
+//!         ..Default::default()
+//!     })
+//!     .unwrap();
+//! println!("stateRoot={}", executed.state_root);
+//! ```
+
+use crate::{BundleError, Event, alloc};
+use alloy_rlp::{Encodable, Header};
+use revm::{
+    context::{
+        TxEnv,
+        result::{ExecutionResult, Output, ResultAndState},
+    },
+    primitives::{Address, B256, Log, U256, alloy_primitives::Bloom, hardfork::SpecId},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A block of transactions (executed in the engine's block environment) - see
+/// [`Engine::execute_block`](crate::Engine::execute_block)
+#[derive(Clone, Debug, Default)]
+pub struct Block {
+    /// Transactions of the block (in order)
+    pub transactions: Vec<TxEnv>,
+    /// Withdrawals (i.e. from the beacon chain) credited after the transactions - see EIP-4895
+    pub withdrawals: Vec<Withdrawal>,
+    /// Reward credited to the block's beneficiary (i.e. coinbase) after the transactions - defaults
+    /// to that of the hardfork (i.e. none as of the merge)
+    pub reward: Option<U256>,
+}
+
+/// A withdrawal (i.e. from the beacon chain) - see EIP-4895
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdrawal {
+    /// Index of the withdrawal
+    #[serde(with = "alloc::quantity")]
+    pub index: u64,
+    /// Index of the validator
+    #[serde(with = "alloc::quantity")]
+    pub validator_index: u64,
+    /// Recipient of the withdrawal
+    pub address: Address,
+    /// Amount of the withdrawal (in gwei)
+    #[serde(with = "alloc::quantity")]
+    pub amount: u64,
+}
+
+/// Receipt of a transaction (of a block)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    /// Type of the transaction - see EIP-2718
+    #[serde(rename = "type")]
+    pub tx_type: u8,
+    /// Whether the transaction succeeded (i.e. rather than reverted or halted) - see EIP-658
+    pub status: bool,
+    /// Root of the state after the transaction, which the receipt commits to instead of its status
+    /// prior to Byzantium (i.e. EIP-658)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<B256>,
+    /// Gas used by the transaction
+    pub gas_used: u64,
+    /// Gas used by the block, up to (and including) the transaction
+    pub cumulative_gas_used: u64,
+    /// Logs emitted by the transaction
+    pub logs: Vec<Log>,
+    /// Bloom filter of the logs
+    pub logs_bloom: Bloom,
+    /// Address of the contract created by the transaction (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<Address>,
+}
+
+impl Receipt {
+    pub(crate) fn new(
+        tx_type: u8,
+        result: &ExecutionResult,
+        cumulative_gas_used: u64,
+        root: Option<B256>,
+    ) -> Self {
+        let logs = result.logs().to_vec();
+        let mut logs_bloom = Bloom::ZERO;
+        logs_bloom.accrue_logs(&logs);
+
+        Self {
+            tx_type,
+            status: result.is_success(),
+            root,
+            gas_used: result.gas_used(),
+            cumulative_gas_used,
+            logs,
+            logs_bloom,
+            contract_address: match result {
+                ExecutionResult::Success {
+                    output: Output::Create(_, address),
+                    ..
+                } => *address,
+                _ => None,
+            },
+        }
+    }
+
+    /// Encodes the receipt as per EIP-2718 - i.e. the type (if typed) followed by the RLP encoded
+    /// status (or state root, if any), cumulative gas used, logs bloom and logs
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        if self.tx_type != 0 {
+            out.push(self.tx_type);
+        }
+        let outcome_length = match &self.root {
+            Some(root) => root.length(),
+            None => self.status.length(),
+        };
+        let payload_length = outcome_length
+            + self.cumulative_gas_used.length()
+            + self.logs_bloom.length()
+            + self.logs.length();
+        Header {
+            list: true,
+            payload_length,
+        }
+        .encode(out);
+        match &self.root {
+            Some(root) => root.encode(out),
+            None => self.status.encode(out),
+        }
+        self.cumulative_gas_used.encode(out);
+        self.logs_bloom.encode(out);
+        self.logs.encode(out);
+    }
+}
+
+/// A block executed by the engine - see [`Engine::execute_block`](crate::Engine::execute_block)
+#[derive(Debug)]
+pub struct ExecutedBlock {
+    /// Result and events of each transaction (in order)
+    pub transactions: Vec<(ResultAndState, Vec<Event>)>,
+    /// Receipt of each transaction (in order)
+    pub receipts: Vec<Receipt>,
+    /// Gas used by the block
+    pub gas_used: u64,
+    /// Bloom filter of the logs of the block
+    pub logs_bloom: Bloom,
+    /// Root of the (Merkle Patricia) trie of the receipts
+    pub receipts_root: B256,
+    /// Root of the (Merkle Patricia) trie of the resulting state
+    pub state_root: B256,
+}
+
+impl ExecutedBlock {
+    pub(crate) fn new(
+        transactions: Vec<(ResultAndState, Vec<Event>)>,
+        receipts: Vec<Receipt>,
+        state_root: B256,
+    ) -> Self {
+        let mut logs_bloom = Bloom::ZERO;
+        for receipt in &receipts {
+            logs_bloom.accrue_bloom(&receipt.logs_bloom);
+        }
+
+        Self {
+            transactions,
+            gas_used: receipts
+                .last()
+                .map_or(0, |receipt| receipt.cumulative_gas_used),
+            logs_bloom,
+            receipts_root: alloy_trie::root::ordered_trie_root_with_encoder(
+                &receipts,
+                Receipt::encode,
+            ),
+            receipts,
+            state_root,
+        }
+    }
+}
+
+/// Error executing a block
+#[derive(Debug)]
+pub enum BlockError<E> {
+    /// A transaction could not be executed (e.g. an invalid nonce, or exceeding the block's gas)
+    Transaction(BundleError<E>),
+    /// An account (e.g. the recipient of a withdrawal) could not be loaded
+    Database(E),
+}
+
+impl<E: fmt::Display> fmt::Display for BlockError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transaction(err) => err.fmt(f),
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for BlockError<E> {}
+
+impl<E> From<BundleError<E>> for BlockError<E> {
+    fn from(err: BundleError<E>) -> Self {
+        Self::Transaction(err)
+    }
+}
+
+/// The block reward of a hardfork (i.e. excluding ommers, and none as of the merge)
+pub fn reward(spec: SpecId) -> U256 {
+    // NOTE: the reward was reduced by EIP-649 (Byzantium) and EIP-1234 (Constantinople)
+    let eth: u64 = match spec {
+        _ if spec.is_enabled_in(SpecId::MERGE) => 0,
+        _ if spec.is_enabled_in(SpecId::CONSTANTINOPLE) => 2,
+        _ if spec.is_enabled_in(SpecId::BYZANTIUM) => 3,
+        _ => 5,
+    };
+    U256::from(eth) * U256::from(1_000_000_000_000_000_000_u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, block};
+    use revm::{
+        bytecode::Bytecode,
+        context::{
+            BlockEnv,
+            result::{EVMError, InvalidTransaction},
+        },
+        primitives::{Bytes, TxKind, address, alloy_primitives::BloomInput, b256, hex},
+        state::AccountInfo,
+    };
+
+    #[test]
+    fn execute_block() {
+        let coinbase = address!("00000000000000000000000000000000000000c0");
+        let mut engine = Engine::new().with_block(BlockEnv {
+            beneficiary: coinbase,
+            gas_limit: 100_000,
+            ..Default::default()
+        });
+
+        // Emits a log (with topic 0x01) of the calldata
+        let logger = address!("00000000000000000000000000000000000000aa");
+        engine.create_account(
+            logger,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&hex!(
+                "60013660005f37365fa100"
+            )))),
+        );
+        let caller = address!("00000000000000000000000000000000000000cc");
+        let tx = |nonce: u64, gas_limit: u64| TxEnv {
+            caller,
+            kind: TxKind::Call(logger),
+            data: Bytes::from_static(&[0x2a]),
+            gas_limit,
+            nonce,
+            ..Default::default()
+        };
+
+        let executed = engine
+            .execute_block(Block {
+                transactions: vec![tx(0, 30_000), tx(1, 30_000)],
+                withdrawals: vec![block::Withdrawal {
+                    address: address!("00000000000000000000000000000000000000dd"),
+                    amount: 1,
+                    ..Default::default()
+                }],
+                reward: None,
+            })
+            .unwrap();
+
+        assert_eq!(executed.receipts.len(), 2);
+        let receipt = &executed.receipts[1];
+        assert!(receipt.status);
+        assert_eq!(receipt.gas_used, 21797);
+        assert_eq!(receipt.cumulative_gas_used, 2 * receipt.gas_used);
+        assert_eq!(executed.gas_used, receipt.cumulative_gas_used);
+        assert_eq!(receipt.logs[0].address, logger);
+        assert_eq!(receipt.logs[0].data.data, Bytes::from_static(&[0x2a]));
+        assert!(
+            executed
+                .logs_bloom
+                .contains_input(BloomInput::Raw(logger.as_slice()))
+        );
+        assert_eq!(
+            executed.receipts_root,
+            b256!("84d0b5ef29696ce24d70ebb3111db10f3dfd376e8b395a31203e54ef1e6e653b")
+        );
+
+        // The withdrawal (in gwei) is credited, whereas there is no block reward (as of the merge)
+        let alloc = engine.dump_alloc();
+        assert_eq!(
+            alloc[&address!("00000000000000000000000000000000000000dd")].balance,
+            U256::from(1_000_000_000)
+        );
+        assert!(!alloc.contains_key(&coinbase));
+        assert_eq!(executed.state_root, engine.state_root());
+
+        // A transaction cannot exceed the gas remaining in the block (reverting the whole block)
+        assert!(matches!(
+            engine.execute_block(Block {
+                transactions: vec![tx(2, 60_000), tx(3, 80_000)],
+                ..Default::default()
+            }),
+            Err(BlockError::Transaction(BundleError {
+                index: 1,
+                error: EVMError::Transaction(InvalidTransaction::CallerGasLimitMoreThanBlock),
+            }))
+        ));
+        assert_eq!(engine.state_root(), executed.state_root);
+
+        // Prior to the merge, the beneficiary is rewarded (e.g. 2 ETH as of Constantinople)
+        let mut engine = engine.with_spec(SpecId::PETERSBURG);
+        engine.execute_block(Block::default()).unwrap();
+        assert_eq!(
+            engine.dump_alloc()[&coinbase].balance,
+            U256::from(2_000_000_000_000_000_000_u64)
+        );
+    }
+
+    #[test]
+    fn pre_byzantium_receipts() {
+        let caller = address!("00000000000000000000000000000000000000cc");
+        let tx = |nonce: u64| TxEnv {
+            caller,
+            kind: TxKind::Call(address!("00000000000000000000000000000000000000aa")),
+            value: U256::from(nonce + 1),
+            gas_limit: 21_000,
+            nonce,
+            ..Default::default()
+        };
+        let block = BlockEnv {
+            beneficiary: address!("00000000000000000000000000000000000000c0"),
+            gas_limit: 100_000,
+            ..Default::default()
+        };
+        let new_engine = || {
+            let mut engine = Engine::new()
+                .with_spec(SpecId::SPURIOUS_DRAGON)
+                .with_block(block.clone());
+            engine.create_account(caller, AccountInfo::from_balance(U256::from(10)));
+            engine
+        };
+
+        // The state roots after each transaction (i.e. before the block reward)
+        let mut engine = new_engine();
+        let state_roots: Vec<_> = (0..2)
+            .map(|nonce| {
+                engine.execute_and_commit(tx(nonce)).unwrap();
+                engine.state_root()
+            })
+            .collect();
+
+        let mut engine = new_engine();
+        let executed = engine
+            .execute_block(Block {
+                transactions: vec![tx(0), tx(1)],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_ne!(executed.state_root, state_roots[1]);
+
+        // Prior to Byzantium, receipts commit to the state root (i.e. rather than the status)
+        for (receipt, state_root) in executed.receipts.iter().zip(&state_roots) {
+            assert_eq!(receipt.root, Some(*state_root));
+            let mut out = Vec::new();
+            receipt.encode(&mut out);
+            let mut expected = Vec::new();
+            alloy_rlp::encode_list::<_, dyn Encodable>(
+                &[
+                    state_root as &dyn Encodable,
+                    &receipt.cumulative_gas_used,
+                    &receipt.logs_bloom,
+                    &receipt.logs,
+                ],
+                &mut expected,
+            );
+            assert_eq!(out, expected);
+        }
+        assert_eq!(
+            executed.receipts_root,
+            alloy_trie::root::ordered_trie_root_with_encoder(&executed.receipts, Receipt::encode)
+        );
+
+        // As of Byzantium, they commit to the status
+        let mut engine = new_engine().with_spec(SpecId::BYZANTIUM);
+        let executed = engine
+            .execute_block(Block {
+                transactions: vec![tx(0)],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(executed.receipts[0].root, None);
+    }
+}
@@ -0,0 +1,133 @@
+//! EIP-3155 compliant trace output - see <https://eips.ethereum.org/EIPS/eip-3155>
+//!
+//! Writes the [`Step`]s of a trace as JSON lines (in the field order and encoding of geth's
+//! `evm run --json`), followed by a single [`Summary`] line.
+
+use crate::{Event, Step};
+use revm::{
+    context::result::ExecutionResult,
+    primitives::{B256, Bytes, U256, hardfork::SpecId},
+};
+use serde::{Serialize, Serializer};
+use std::{io, time::Duration};
+
+fn hex<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{value:#x}"))
+}
+
+/// A single step (i.e. line) of the trace, borrowed from a [`Step`]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Line<'a> {
+    pc: usize,
+    op: u8,
+    #[serde(serialize_with = "hex")]
+    gas: u64,
+    #[serde(serialize_with = "hex")]
+    gas_cost: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<&'a str>,
+    mem_size: usize,
+    stack: &'a [U256],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    return_data: Option<&'a Bytes>,
+    depth: u64,
+    refund: u64,
+    op_name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+impl<'a> From<&'a Step> for Line<'a> {
+    fn from(step: &'a Step) -> Self {
+        Self {
+            pc: step.pc,
+            op: step.op,
+            gas: step.gas,
+            gas_cost: step.gas_cost,
+            memory: step.memory.as_deref(),
+            mem_size: step.mem_size,
+            stack: step.stack.as_deref().unwrap_or_default(),
+            return_data: step.return_data.as_ref(),
+            depth: step.depth,
+            refund: step.refund,
+            op_name: step.op_name,
+            error: step.error.as_deref(),
+        }
+    }
+}
+
+/// The final line of the trace, summarizing the outcome of the transaction
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "stateRoot": "0x0000000000000000000000000000000000000000000000000000000000000000",
+///   "output": "0x40",
+///   "gasUsed": "0x60a8",
+///   "pass": true,
+///   "time": 141709,
+///   "fork": "Prague"
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    /// Root of the state trie after executing the transaction (zero unless provided - see
+    /// [`Summary::with_state_root`])
+    state_root: B256,
+    /// Return values of the transaction
+    output: Bytes,
+    /// Gas used by the transaction
+    #[serde(serialize_with = "hex")]
+    gas_used: u64,
+    /// Whether the transaction succeeded
+    pass: bool,
+    /// Time taken to execute the transaction (in nanoseconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<u128>,
+    /// Name of the hardfork the transaction was executed against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fork: Option<String>,
+}
+
+impl Summary {
+    /// Constructs a summary of the result of a transaction executed against the given hardfork
+    pub fn new(result: &ExecutionResult, spec: SpecId) -> Self {
+        Self {
+            state_root: B256::ZERO,
+            output: result.output().cloned().unwrap_or_default(),
+            gas_used: result.gas_used(),
+            pass: result.is_success(),
+            time: None,
+            fork: Some(spec.to_string()),
+        }
+    }
+
+    /// Includes the root of the state trie after executing (and committing) the transaction - see
+    /// [`Engine::state_root`](crate::Engine::state_root)
+    pub fn with_state_root(mut self, state_root: B256) -> Self {
+        self.state_root = state_root;
+        self
+    }
+
+    /// Includes the time taken to execute the transaction
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = Some(time.as_nanos());
+        self
+    }
+}
+
+/// Writes the steps of a trace (other events are omitted) and its summary as JSON lines
+pub fn write(mut writer: impl io::Write, events: &[Event], summary: &Summary) -> io::Result<()> {
+    for event in events {
+        if let Event::Step(step) = event {
+            serde_json::to_writer(&mut writer, &Line::from(step))?;
+            writer.write_all(b"\n")?;
+        }
+    }
+
+    serde_json::to_writer(&mut writer, summary)?;
+    writer.write_all(b"\n")
+}
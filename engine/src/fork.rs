@@ -0,0 +1,592 @@
+//! Backend database lazily loading state from a JSON-RPC endpoint (i.e. a "fork")
+//!
+//! The [`ForkDb`] fetches account info, code, storage and block hashes on demand, as of a pinned
+//! block, from a JSON-RPC endpoint (e.g. geth or a node provider). Everything fetched is cached,
+//! optionally on disk, so a trace can be replayed offline (i.e. without the endpoint).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use engine::{Engine, fork::ForkDb};
+//! let db = ForkDb::new("http://localhost:8545", 19_000_000).with_cache("fork.json")?;
+//! let block = db.block_env()?;
+//! let mut engine = Engine::new().with_block(block).with_db(db);
+//! # Ok::<(), engine::fork::ForkError>(())
+//! ```
+
+use revm::{
+    DatabaseRef,
+    bytecode::Bytecode,
+    context::BlockEnv,
+    database::DBErrorMarker,
+    primitives::{Address, B256, Bytes, U256, alloy_primitives::U64},
+    state::AccountInfo,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+/// Error fetching state for a [`ForkDb`]
+#[derive(Debug)]
+pub enum ForkError {
+    /// The endpoint could not be reached (or responded with an invalid response)
+    Transport(String),
+    /// The endpoint responded with a JSON-RPC error
+    Rpc {
+        /// Code of the error
+        code: i64,
+        /// Description of the error
+        message: String,
+    },
+    /// The state is not cached, and cannot be fetched (i.e. offline)
+    Offline(String),
+    /// The cache could not be read or written
+    Cache(String),
+}
+
+impl fmt::Display for ForkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(message) => write!(f, "transport error: {message}"),
+            Self::Rpc { code, message } => write!(f, "rpc error {code}: {message}"),
+            Self::Offline(what) => write!(f, "not cached (offline): {what}"),
+            Self::Cache(message) => write!(f, "cache error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ForkError {}
+
+impl DBErrorMarker for ForkError {}
+
+impl From<io::Error> for ForkError {
+    fn from(err: io::Error) -> Self {
+        Self::Cache(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ForkError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Cache(err.to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for ForkError {
+    fn from(err: PoisonError<T>) -> Self {
+        Self::Cache(err.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Account {
+    balance: U256,
+    nonce: u64,
+    code: Bytes,
+}
+
+/// The (subset of the) block header relevant to the [`BlockEnv`]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    number: U64,
+    hash: B256,
+    timestamp: U64,
+    miner: Address,
+    gas_limit: U64,
+    #[serde(default)]
+    base_fee_per_gas: Option<U64>,
+    #[serde(default)]
+    mix_hash: Option<B256>,
+    #[serde(default)]
+    difficulty: U256,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Cache {
+    block: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    header: Option<Header>,
+    #[serde(default)]
+    accounts: BTreeMap<Address, Account>,
+    #[serde(default)]
+    storage: BTreeMap<Address, BTreeMap<U256, U256>>,
+    #[serde(default)]
+    block_hashes: BTreeMap<u64, B256>,
+    #[serde(skip)]
+    contracts: BTreeMap<B256, Bytecode>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+/// A backend database lazily loading state from a JSON-RPC endpoint at a pinned block
+pub struct ForkDb {
+    url: Option<String>,
+    path: Option<PathBuf>,
+    cache: Mutex<Cache>,
+}
+
+impl ForkDb {
+    /// Constructs a backend fetching state from the JSON-RPC endpoint (`url`) as of the `block`
+    pub fn new(url: impl Into<String>, block: u64) -> Self {
+        Self {
+            url: Some(url.into()),
+            path: None,
+            cache: Mutex::new(Cache {
+                block,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Constructs a backend replaying state from the cache (`path`) only - i.e. without an endpoint
+    pub fn offline(path: impl AsRef<Path>) -> Result<Self, ForkError> {
+        let path = path.as_ref();
+        let cache = Self::load(path)?
+            .ok_or_else(|| ForkError::Cache(format!("not found: {}", path.display())))?;
+
+        Ok(Self {
+            url: None,
+            path: Some(path.into()),
+            cache: Mutex::new(cache),
+        })
+    }
+
+    /// Caches the fetched state on disk (`path`), loading any state cached (for the same block)
+    /// previously - see [`ForkDb::flush`]
+    pub fn with_cache(mut self, path: impl AsRef<Path>) -> Result<Self, ForkError> {
+        let path = path.as_ref();
+        if let Some(cache) = Self::load(path)? {
+            let block = self.block();
+            if cache.block != block {
+                return Err(ForkError::Cache(format!(
+                    "cached block {} does not match block {block}",
+                    cache.block
+                )));
+            }
+            *self.cache.get_mut()? = cache;
+        }
+        self.path = Some(path.into());
+        Ok(self)
+    }
+
+    /// Fetches the number of the latest block of the JSON-RPC endpoint (`url`)
+    pub fn latest_block(url: &str) -> Result<u64, ForkError> {
+        Ok(request::<U64>(url, "eth_blockNumber", json!([]))?.to())
+    }
+
+    /// The (pinned) block the state is fetched as of
+    pub fn block(&self) -> u64 {
+        // NOTE: the block is never changed, so is unaffected by a poisoned lock
+        self.cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .block
+    }
+
+    /// Fetches the block environment (e.g. timestamp, coinbase and base fee) of the pinned block
+    pub fn block_env(&self) -> Result<BlockEnv, ForkError> {
+        let header = self.cached(
+            |cache| cache.header.clone(),
+            |db, block| db.request("eth_getBlockByNumber", json!([quantity(block), false])),
+            |cache, header: &Header| {
+                cache.block_hashes.insert(header.number.to(), header.hash);
+                cache.header = Some(header.clone());
+            },
+            "block header",
+        )?;
+
+        Ok(BlockEnv {
+            number: header.number.to(),
+            beneficiary: header.miner,
+            timestamp: header.timestamp.to(),
+            gas_limit: header.gas_limit.to(),
+            basefee: header
+                .base_fee_per_gas
+                .map(|fee| fee.to())
+                .unwrap_or_default(),
+            difficulty: header.difficulty,
+            prevrandao: header.mix_hash,
+            ..Default::default()
+        })
+    }
+
+    /// Writes the cached state to disk (if configured and changed)
+    pub fn flush(&self) -> Result<(), ForkError> {
+        let mut cache = self.cache.lock()?;
+        if let (Some(path), true) = (&self.path, cache.dirty) {
+            fs::write(path, serde_json::to_vec_pretty(&*cache)?)?;
+            cache.dirty = false;
+        }
+        Ok(())
+    }
+
+    fn load(path: &Path) -> Result<Option<Cache>, ForkError> {
+        match fs::read(path) {
+            Ok(bytes) => {
+                let mut cache: Cache = serde_json::from_slice(&bytes)?;
+                for account in cache.accounts.values() {
+                    let code = Bytecode::new_raw(account.code.clone());
+                    cache.contracts.insert(code.hash_slow(), code);
+                }
+                Ok(Some(cache))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, ForkError> {
+        match &self.url {
+            Some(url) => request(url, method, params),
+            None => Err(ForkError::Offline(format!("{method} {params}"))),
+        }
+    }
+
+    /// Looks up a value in the cache, otherwise fetching (as of the pinned block) and caching it
+    fn cached<T>(
+        &self,
+        lookup: impl FnOnce(&Cache) -> Option<T>,
+        fetch: impl FnOnce(&Self, u64) -> Result<T, ForkError>,
+        insert: impl FnOnce(&mut Cache, &T),
+        what: &str,
+    ) -> Result<T, ForkError> {
+        let block = {
+            let cache = self.cache.lock()?;
+            if let Some(value) = lookup(&cache) {
+                return Ok(value);
+            }
+            cache.block
+        };
+        if self.url.is_none() {
+            return Err(ForkError::Offline(what.into()));
+        }
+
+        // NOTE: the lock is not held whilst fetching, so concurrent lookups are not blocked by the
+        // endpoint (although the same value may be fetched, and cached, more than once)
+        let value = fetch(self, block)?;
+        let mut cache = self.cache.lock()?;
+        insert(&mut cache, &value);
+        cache.dirty = true;
+        Ok(value)
+    }
+}
+
+impl Drop for ForkDb {
+    fn drop(&mut self) {
+        // NOTE: best effort only - call `flush` explicitly to handle errors
+        let _ = self.flush();
+    }
+}
+
+impl DatabaseRef for ForkDb {
+    type Error = ForkError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let account = self.cached(
+            |cache| cache.accounts.get(&address).cloned(),
+            |db, block| {
+                let block = quantity(block);
+                Ok(Account {
+                    balance: db.request("eth_getBalance", json!([address, block]))?,
+                    nonce: db
+                        .request::<U64>("eth_getTransactionCount", json!([address, block]))?
+                        .to(),
+                    code: db.request("eth_getCode", json!([address, block]))?,
+                })
+            },
+            |cache, account| {
+                let code = Bytecode::new_raw(account.code.clone());
+                cache.contracts.insert(code.hash_slow(), code);
+                cache.accounts.insert(address, account.clone());
+            },
+            &format!("account {address}"),
+        )?;
+
+        // NOTE: an empty account is absent from the state (i.e. as per `eth_getProof`), rather than
+        // an existing account to be committed to by the state root
+        if account.balance.is_zero() && account.nonce == 0 && account.code.is_empty() {
+            return Ok(None);
+        }
+
+        let code = Bytecode::new_raw(account.code);
+        Ok(Some(AccountInfo::new(
+            account.balance,
+            account.nonce,
+            code.hash_slow(),
+            code,
+        )))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // NOTE: code cannot be fetched by hash, but is always fetched alongside its account
+        self.cache
+            .lock()?
+            .contracts
+            .get(&code_hash)
+            .cloned()
+            .ok_or_else(|| ForkError::Offline(format!("code {code_hash}")))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.cached(
+            |cache| cache.storage.get(&address)?.get(&index).copied(),
+            |db, block| {
+                let value: B256 =
+                    db.request("eth_getStorageAt", json!([address, index, quantity(block)]))?;
+                Ok(U256::from_be_bytes(value.0))
+            },
+            |cache, value| {
+                cache
+                    .storage
+                    .entry(address)
+                    .or_default()
+                    .insert(index, *value);
+            },
+            &format!("storage {address} {index}"),
+        )
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.cached(
+            |cache| cache.block_hashes.get(&number).copied(),
+            |db, _block| {
+                let header: Header =
+                    db.request("eth_getBlockByNumber", json!([quantity(number), false]))?;
+                Ok(header.hash)
+            },
+            |cache, hash| {
+                cache.block_hashes.insert(number, *hash);
+            },
+            &format!("block hash {number}"),
+        )
+    }
+}
+
+/// Encodes a number as a (hex) JSON-RPC quantity
+fn quantity(number: u64) -> String {
+    format!("{number:#x}")
+}
+
+/// Sends a JSON-RPC request to the endpoint (`url`)
+fn request<T: DeserializeOwned>(url: &str, method: &str, params: Value) -> Result<T, ForkError> {
+    #[derive(Deserialize)]
+    struct Error {
+        code: i64,
+        message: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Response {
+        #[serde(default)]
+        result: Option<Value>,
+        #[serde(default)]
+        error: Option<Error>,
+    }
+
+    let response: Response = ureq::post(url)
+        .send_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        }))
+        .map_err(|err| ForkError::Transport(err.to_string()))?
+        .into_json()
+        .map_err(|err| ForkError::Transport(err.to_string()))?;
+
+    match response {
+        Response {
+            error: Some(Error { code, message }),
+            ..
+        } => Err(ForkError::Rpc { code, message }),
+        Response {
+            result: Some(result),
+            ..
+        } => serde_json::from_value(result).map_err(|err| ForkError::Transport(err.to_string())),
+        _ => Err(ForkError::Transport(format!("{method}: missing result"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use revm::{
+        context::{TxEnv, result::EVMError},
+        primitives::{TxKind, address},
+    };
+    use std::sync::mpsc;
+
+    /// Serves JSON-RPC requests (over HTTP) from the handler, returning the URL and the requests
+    /// served (i.e. their method and params)
+    ///
+    /// NOTE: the requests are checked by the test (rather than the handler), as a panic on the
+    /// server would only surface as a transport error
+    fn mock_rpc(
+        handler: fn(&str, &[serde_json::Value]) -> serde_json::Value,
+    ) -> (String, mpsc::Receiver<(String, Vec<serde_json::Value>)>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let requests = sender.clone();
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    // NOTE: connections are kept alive (i.e. serve requests until closed)
+                    loop {
+                        let mut length = 0;
+                        loop {
+                            let mut line = String::new();
+                            if reader.read_line(&mut line).unwrap() == 0 {
+                                return;
+                            }
+                            let line = line.trim_end().to_ascii_lowercase();
+                            if line.is_empty() {
+                                break;
+                            }
+                            if let Some(value) = line.strip_prefix("content-length:") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+
+                        let mut body = vec![0; length];
+                        reader.read_exact(&mut body).unwrap();
+                        let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                        let method = request["method"].as_str().unwrap();
+                        let params = request["params"].as_array().unwrap();
+                        let _ = requests.send((method.to_string(), params.clone()));
+
+                        let body = serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "result": handler(method, params),
+                        })
+                        .to_string();
+                        write!(
+                            stream,
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                            body.len()
+                        )
+                        .unwrap();
+                    }
+                });
+            }
+        });
+
+        (url, receiver)
+    }
+
+    #[test]
+    fn fork_db() {
+        // Returns the value of slot 5 of the forked contract
+        let (url, requests) = mock_rpc(|method, params| {
+            let contract = "0x00000000000000000000000000000000000000aa";
+            match method {
+                "eth_getBlockByNumber" => serde_json::json!({
+                    "number": params[0],
+                    "hash": revm::primitives::keccak256(params[0].as_str().unwrap()),
+                    "timestamp": "0x6553f100",
+                    "miner": "0x00000000000000000000000000000000000000cb",
+                    "gasLimit": "0x1c9c380",
+                    "baseFeePerGas": "0x7",
+                    "mixHash": "0x000000000000000000000000000000000000000000000000000000000000002a",
+                    "difficulty": "0x0",
+                }),
+                _ => match (method, params[0] == contract) {
+                    ("eth_getBalance", _) => "0x0".into(),
+                    ("eth_getTransactionCount", _) => "0x0".into(),
+                    ("eth_getCode", true) => "0x60055460005260205ff3".into(),
+                    ("eth_getCode", false) => "0x".into(),
+                    ("eth_getStorageAt", _) => {
+                        "0x000000000000000000000000000000000000000000000000000000000000002a".into()
+                    }
+                    _ => serde_json::Value::Null,
+                },
+            }
+        });
+
+        let path = std::env::temp_dir().join(format!("engine-fork-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let call = |engine: &mut Engine<ForkDb>, address: Address| {
+            engine.execute(TxEnv {
+                kind: TxKind::Call(address),
+                ..Default::default()
+            })
+        };
+        let contract = address!("00000000000000000000000000000000000000aa");
+
+        let db = ForkDb::new(url, 100).with_cache(&path).unwrap();
+        let block = db.block_env().unwrap();
+        assert_eq!(block.number, 100);
+        assert_eq!(block.timestamp, 0x6553f100);
+        assert_eq!(block.basefee, 7);
+        assert_eq!(block.prevrandao, Some(B256::with_last_byte(0x2a)));
+
+        let mut engine = Engine::new()
+            .with_block(block)
+            .with_base_fee_check(false)
+            .with_db(db);
+        let (res, _events) = call(&mut engine, contract).unwrap();
+        assert_eq!(
+            res.result.output(),
+            Some(&Bytes::from(U256::from(0x2a).to_be_bytes::<32>()))
+        );
+
+        // Empty accounts (e.g. the caller) are absent, rather than created by being touched
+        assert!(!engine.dump_alloc().contains_key(&Address::ZERO));
+        let absent = engine
+            .proof(address!("00000000000000000000000000000000000000dd"), &[])
+            .unwrap();
+        assert_eq!(crate::trie::verify(engine.state_root(), &absent), Ok(()));
+        engine.db().db.flush().unwrap();
+
+        // NOTE: state is fetched as of the pinned block
+        let fetched: Vec<_> = requests.try_iter().collect();
+        assert!(fetched.len() > 1);
+        for (method, params) in &fetched {
+            match method.as_str() {
+                "eth_getBlockByNumber" => assert_eq!(params[0], "0x64"),
+                "eth_getBalance"
+                | "eth_getTransactionCount"
+                | "eth_getCode"
+                | "eth_getStorageAt" => {
+                    assert_eq!(params.last().unwrap(), "0x64", "{method}: {params:?}")
+                }
+                _ => panic!("unexpected method: {method}"),
+            }
+        }
+
+        // Replaying (offline) from the cache does not fetch anything
+        let db = ForkDb::offline(&path).unwrap();
+        assert_eq!(db.block(), 100);
+        let mut engine = Engine::new()
+            .with_block(db.block_env().unwrap())
+            .with_base_fee_check(false)
+            .with_db(db);
+        let (replayed, _events) = call(&mut engine, contract).unwrap();
+        assert_eq!(replayed.result, res.result);
+        assert_eq!(requests.try_iter().count(), 0);
+
+        assert!(matches!(
+            call(
+                &mut engine,
+                address!("00000000000000000000000000000000000000bb")
+            ),
+            Err(EVMError::Database(ForkError::Offline(_)))
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
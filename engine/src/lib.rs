@@ -44,29 +44,150 @@
 
 #![deny(missing_docs)]
 
+pub mod alloc;
+pub mod block;
+pub mod eip3155;
+pub mod fork;
+pub mod opcodes;
+pub mod raw;
+pub mod statetest;
+pub mod trie;
+
+use alloc::{Alloc, GenesisAccount};
+use block::{Block, BlockError, ExecutedBlock, Receipt};
 use revm::{
-    Context, InspectEvm, MainContext,
+    Database, DatabaseCommit, InspectEvm, MainContext,
+    bytecode::{Bytecode, OpCode, opcode},
     context::{
-        ContextTr, Evm, JournalTr, TxEnv,
-        result::{EVMError, ResultAndState},
+        BlockEnv, CfgEnv, ContextTr, Evm, EvmData, JournalEntry, JournalTr, TxEnv,
+        result::{EVMError, ExecutionResult, InvalidTransaction, ResultAndState},
     },
-    database::EmptyDB,
+    context_interface::{
+        self,
+        transaction::{AccessList, AccessListItem, TransactionType},
+    },
+    database::{AccountState, Cache, CacheDB, DatabaseRef, DbAccount, EmptyDB},
     handler::{EthPrecompiles, instructions::EthInstructions},
-    inspector::{InspectorEvmTr, inspectors::GasInspector},
+    inspector::{InspectorEvmTr, JournalExt, inspectors::GasInspector},
     interpreter::{
-        CallInputs, CallOutcome, CreateInputs, CreateOutcome, EOFCreateInputs, Interpreter,
+        self, CallInputs, CallOutcome, CreateInputs, CreateOutcome, EOFCreateInputs, EOFCreateKind,
+        InstructionResult, Interpreter,
         interpreter::EthInterpreter,
-        interpreter_types::{Jumps, LoopControl, MemoryTr},
+        interpreter_types::{Jumps, LoopControl, MemoryTr, ReturnData},
     },
-    primitives::{Address, Log, U256, hex},
-    state::Account,
+    precompile::Precompiles,
+    primitives::{Address, B256, Bytes, TxKind, U256, hardfork::SpecId, hex, keccak256},
+    state::{Account, EvmState},
 };
 use serde::Serialize;
-use std::convert::Infallible;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// The [`revm`] context of the [`Engine`] - i.e. backed by an in-memory database (in front of the
+/// backend database)
+type Context<DB> = revm::Context<BlockEnv, TxEnv, CfgEnv, CacheDB<DB>>;
 
 /// Ethereum Virtual Machine execution engine with event tracing support
-pub struct Engine {
-    evm: Evm<Context, Tracer, EthInstructions<EthInterpreter, Context>, EthPrecompiles>,
+///
+/// The EVM state is held in memory (i.e. a [`CacheDB`]), in front of a backend database (`DB`) from
+/// which any other accounts and storage are loaded - see [`Engine::with_db`].
+pub struct Engine<DB: DatabaseRef = EmptyDB> {
+    evm: Evm<Context<DB>, Tracer, EthInstructions<EthInterpreter, Context<DB>>, EthPrecompiles>,
+    snapshots: Vec<Cache>,
+}
+
+/// Identifier of a snapshot of the [`Engine`]'s EVM state - see [`Engine::snapshot`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SnapshotId(usize);
+
+/// Results of a bundle of transactions - see [`Engine::execute_bundle`]
+#[derive(Debug)]
+pub struct Bundle {
+    /// Result and events of each transaction (in order)
+    pub transactions: Vec<(ResultAndState, Vec<Event>)>,
+    /// Cumulative state changes of the transactions - i.e. each account as of the last transaction
+    /// touching it, with the original values of storage slots as of before the bundle (or as of
+    /// its last self-destruct or creation within the bundle)
+    pub state: EvmState,
+}
+
+/// Error executing a bundle of transactions - i.e. a transaction could not be executed
+#[derive(Debug)]
+pub struct BundleError<E> {
+    /// Index of the transaction (within the bundle)
+    pub index: usize,
+    /// Reason the transaction could not be executed
+    pub error: EVMError<E>,
+}
+
+impl<E: fmt::Display> fmt::Display for BundleError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transaction {}: {}", self.index, self.error)
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for BundleError<E> {}
+
+/// Estimated gas of a transaction, and its (final) execution with it - see
+/// [`Engine::estimate_gas`]
+#[derive(Debug)]
+pub struct GasEstimate {
+    /// Minimal gas limit with which the transaction succeeds
+    pub gas_limit: u64,
+    /// Result of the transaction (with the estimated gas limit)
+    pub result: ResultAndState,
+    /// Events of the transaction (with the estimated gas limit)
+    pub events: Vec<Event>,
+}
+
+/// Error estimating the gas of a transaction
+#[derive(Debug)]
+pub enum EstimateError<E> {
+    /// The transaction could not be executed (e.g. invalid nonce, or insufficient balance)
+    Evm(EVMError<E>),
+    /// The transaction fails (i.e. reverts or halts) even with its gas limit
+    Failed(ExecutionResult),
+}
+
+impl<E: fmt::Display> fmt::Display for EstimateError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Evm(err) => err.fmt(f),
+            Self::Failed(ExecutionResult::Revert { output, .. }) => {
+                write!(f, "execution reverted: {output}")
+            }
+            Self::Failed(ExecutionResult::Halt { reason, .. }) => {
+                write!(f, "execution halted: {reason:?}")
+            }
+            Self::Failed(ExecutionResult::Success { .. }) => write!(f, "execution failed"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for EstimateError<E> {}
+
+impl<E> From<EVMError<E>> for EstimateError<E> {
+    fn from(err: EVMError<E>) -> Self {
+        Self::Evm(err)
+    }
+}
+
+/// Access list of a transaction, and its (final) execution with it - see
+/// [`Engine::create_access_list`]
+#[derive(Debug)]
+pub struct CreatedAccessList {
+    /// Accounts and storage slots accessed by the transaction - see EIP-2930
+    pub access_list: AccessList,
+    /// Gas used by the transaction with the access list
+    pub gas_used: u64,
+    /// Gas saved by the access list (i.e. negative if it costs more than it saves)
+    pub gas_saved: i64,
+    /// Result of the transaction (with the access list)
+    pub result: ResultAndState,
+    /// Events of the transaction (with the access list)
+    pub events: Vec<Event>,
 }
 
 impl Default for Engine {
@@ -80,29 +201,575 @@ impl Engine {
     pub fn new() -> Self {
         Self {
             evm: Evm::new_with_inspector(
-                Context::mainnet().with_db(EmptyDB::default()),
-                Tracer::new(),
+                revm::Context::mainnet().with_db(CacheDB::default()),
+                Tracer::new(TraceConfig::default()),
                 EthInstructions::new_mainnet(),
                 EthPrecompiles::default(),
             ),
+            snapshots: Vec::new(),
+        }
+    }
+}
+
+impl<DB: DatabaseRef> Engine<DB> {
+    /// Replaces the backend database (e.g. a remote node, file or mock) from which accounts and
+    /// storage not created in the engine are loaded
+    ///
+    /// NOTE: the EVM state (i.e. created accounts, committed transactions and snapshots) is reset,
+    /// whereas the configuration (e.g. hardfork, block and trace) is retained.
+    pub fn with_db<ODB: DatabaseRef>(self, db: ODB) -> Engine<ODB> {
+        let Evm {
+            data: EvmData { ctx, inspector },
+            precompiles,
+            ..
+        } = self.evm;
+
+        Engine {
+            evm: Evm::new_with_inspector(
+                ctx.with_db(CacheDB::new(db)),
+                inspector,
+                EthInstructions::new_mainnet(),
+                precompiles,
+            ),
+            snapshots: Vec::new(),
         }
     }
 
-    /// Creates a new account in the engine's EVM state
+    /// The engine's (in-memory) database, in front of the backend database
+    pub fn db(&self) -> &CacheDB<DB> {
+        self.evm.data.ctx.db_ref()
+    }
+
+    /// The engine's (in-memory) database, in front of the backend database (e.g. to seed state)
+    pub fn db_mut(&mut self) -> &mut CacheDB<DB> {
+        self.evm.data.ctx.db()
+    }
+
+    /// Configures the trace captured by the engine for subsequent executions
+    pub fn with_trace_config(mut self, config: TraceConfig) -> Self {
+        self.evm.inspector().config = config;
+        self
+    }
+
+    /// Configures the hardfork (i.e. specification) the engine executes against - e.g.
+    /// [`SpecId::SHANGHAI`] (defaults to [`SpecId::PRAGUE`])
+    pub fn with_spec(mut self, spec: SpecId) -> Self {
+        self.evm.data.ctx.cfg.spec = spec;
+        self
+    }
+
+    /// The hardfork (i.e. specification) the engine executes against
+    pub fn spec(&self) -> SpecId {
+        self.evm.data.ctx.cfg.spec
+    }
+
+    /// Configures the chain ID of the engine, against which that of transactions is checked
+    /// (defaults to `1`, i.e. mainnet)
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.evm.data.ctx.cfg.chain_id = chain_id;
+        self
+    }
+
+    /// Configures the block (e.g. number, timestamp, coinbase and base fee) the engine executes in
+    pub fn with_block(mut self, block: BlockEnv) -> Self {
+        self.evm.data.ctx.block = block;
+        self
+    }
+
+    /// The block the engine executes in
+    pub fn block(&self) -> &BlockEnv {
+        &self.evm.data.ctx.block
+    }
+
+    /// Configures whether transactions must pay (at least) the block's base fee - disable to
+    /// simulate calls with a zero gas price (i.e. as per `eth_call`)
+    pub fn with_base_fee_check(mut self, enabled: bool) -> Self {
+        self.evm.data.ctx.cfg.disable_base_fee = !enabled;
+        self
+    }
+
+    /// Creates a new account in the engine's EVM state (replacing any existing account)
     pub fn create_account(&mut self, address: Address, account: impl Into<Account>) {
-        self.evm.journal().state().insert(address, account.into());
+        let Account {
+            mut info, storage, ..
+        } = account.into();
+
+        let db = self.evm.data.ctx.db();
+        db.insert_contract(&mut info);
+        db.cache.accounts.insert(
+            address,
+            DbAccount {
+                info,
+                // NOTE: storage not provided is empty (i.e. not loaded from the database)
+                account_state: AccountState::StorageCleared,
+                storage: storage
+                    .into_iter()
+                    .map(|(slot, value)| (slot, value.present_value))
+                    .collect(),
+            },
+        );
+    }
+
+    /// Creates the accounts of a state dump (e.g. a genesis `alloc`) in the engine's EVM state
+    pub fn load_alloc(&mut self, alloc: Alloc) {
+        for (address, account) in alloc {
+            self.create_account(address, account);
+        }
+    }
+
+    /// Dumps the engine's EVM state (e.g. as a genesis `alloc`)
+    ///
+    /// NOTE: only accounts held in the engine (i.e. created, committed or loaded from the backend
+    /// database) are dumped. Empty accounts are dumped if they exist - e.g. created, or committed
+    /// prior to Spurious Dragon (see [`Engine::execute_and_commit`]).
+    pub fn dump_alloc(&self) -> Alloc {
+        let db = self.db();
+        db.cache
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.account_state != AccountState::NotExisting)
+            .map(|(address, account)| {
+                let code = match &account.info.code {
+                    Some(code) => code.original_bytes(),
+                    None => db
+                        .cache
+                        .contracts
+                        .get(&account.info.code_hash)
+                        .map(Bytecode::original_bytes)
+                        .unwrap_or_default(),
+                };
+                let account = GenesisAccount {
+                    balance: account.info.balance,
+                    nonce: account.info.nonce,
+                    code,
+                    storage: account
+                        .storage
+                        .iter()
+                        .filter(|(_, value)| !value.is_zero())
+                        .map(|(slot, value)| (*slot, *value))
+                        .collect(),
+                };
+                (*address, account)
+            })
+            .collect()
+    }
+
+    /// Root of the (Merkle Patricia) trie of the engine's EVM state
+    ///
+    /// NOTE: only accounts held in the engine are committed to (as per [`Engine::dump_alloc`]) - i.e.
+    /// the root is not that of the whole state if backed by another database (e.g. a fork).
+    pub fn state_root(&self) -> B256 {
+        trie::state_root(&self.dump_alloc())
+    }
+
+    /// Root of the (Merkle Patricia) trie of the storage of an account held in the engine (i.e. as
+    /// per [`Engine::dump_alloc`]) - `None` if the account is not
+    pub fn storage_root(&self, address: Address) -> Option<B256> {
+        self.db()
+            .cache
+            .accounts
+            .get(&address)
+            .filter(|account| account.account_state != AccountState::NotExisting)
+            .map(|account| trie::storage_root(&account.storage))
+    }
+
+    /// Proves an account and slots of its storage against the engine's state root (see
+    /// [`Engine::state_root`]) - equivalent to `eth_getProof`, and verified by [`trie::verify`]
+    ///
+    /// NOTE: the account and slots are loaded from the backend database (if not held in the engine),
+    /// such that they are committed to by the state root.
+    pub fn proof(
+        &mut self,
+        address: Address,
+        slots: &[U256],
+    ) -> Result<trie::AccountProof, DB::Error> {
+        let db = self.db_mut();
+        db.load_account(address)?;
+        for slot in slots {
+            db.storage(address, *slot)?;
+        }
+        Ok(trie::prove(&self.dump_alloc(), address, slots))
+    }
+
+    /// Takes a snapshot of the engine's EVM state (i.e. accounts and storage), to be restored by
+    /// [`Engine::revert_to`] - equivalent to `evm_snapshot`
+    pub fn snapshot(&mut self) -> SnapshotId {
+        self.snapshots.push(self.evm.data.ctx.db().cache.clone());
+        SnapshotId(self.snapshots.len() - 1)
+    }
+
+    /// Restores the EVM state of a snapshot - equivalent to `evm_revert`
+    ///
+    /// The snapshot (and any taken after it) is consumed, so take another snapshot to revert to the
+    /// same state again. Returns `false` if the snapshot does not exist (e.g. already reverted).
+    pub fn revert_to(&mut self, id: SnapshotId) -> bool {
+        if id.0 >= self.snapshots.len() {
+            return false;
+        }
+        self.snapshots.truncate(id.0 + 1);
+        self.evm.data.ctx.db().cache = self.snapshots.pop().unwrap();
+        true
     }
 
     /// Executes a transaction and returns the result and associated events
     pub fn execute(
         &mut self,
         tx: TxEnv,
-    ) -> Result<(ResultAndState, Vec<Event>), EVMError<Infallible>> {
+    ) -> Result<(ResultAndState, Vec<Event>), EVMError<DB::Error>> {
         // NOTE(toms): gas costs will include 'base stipend' (21000)
+        self.evm.inspector().reset();
         let res = self.evm.inspect_with_tx(tx)?;
         let events = self.evm.inspector().events.split_off(0);
         Ok((res, events))
     }
+
+    /// Executes a bundle of transactions in order, committing each (as per
+    /// [`Engine::execute_and_commit`]) such that it sees the state changes of those before it
+    ///
+    /// NOTE: a bundle is atomic - i.e. if any transaction cannot be executed (e.g. an invalid nonce),
+    /// the state changes of the whole bundle are reverted. Transactions which revert (or halt) are
+    /// executed nonetheless.
+    pub fn execute_bundle(
+        &mut self,
+        txs: impl IntoIterator<Item = TxEnv>,
+    ) -> Result<Bundle, BundleError<DB::Error>> {
+        self.execute_bundle_with(txs, |_engine, _bundle, _tx| Ok(()))
+    }
+
+    /// Executes a bundle of transactions (as per [`Engine::execute_bundle`]), checking each before
+    /// its execution (e.g. against the gas used by those before it)
+    fn execute_bundle_with(
+        &mut self,
+        txs: impl IntoIterator<Item = TxEnv>,
+        mut check: impl FnMut(&Self, &Bundle, &TxEnv) -> Result<(), InvalidTransaction>,
+    ) -> Result<Bundle, BundleError<DB::Error>> {
+        let snapshot = self.snapshot();
+
+        let mut bundle = Bundle {
+            transactions: Vec::new(),
+            state: EvmState::default(),
+        };
+        for (index, tx) in txs.into_iter().enumerate() {
+            let executed = check(self, &bundle, &tx)
+                .map_err(EVMError::Transaction)
+                .and_then(|()| self.execute_and_commit(tx));
+            let (res, events) = match executed {
+                Ok(executed) => executed,
+                Err(error) => {
+                    self.revert_to(snapshot);
+                    return Err(BundleError { index, error });
+                }
+            };
+
+            for (address, account) in &res.state {
+                // NOTE: a self-destructed (or re-created) account replaces any earlier changes, as
+                // its storage (as of before the transaction) no longer exists
+                let merged = match bundle.state.get_mut(address) {
+                    Some(merged) if !account.is_selfdestructed() && !account.is_created() => merged,
+                    _ => {
+                        bundle.state.insert(*address, account.clone());
+                        continue;
+                    }
+                };
+                merged.info = account.info.clone();
+                merged.status |= account.status;
+                for (slot, value) in &account.storage {
+                    merged
+                        .storage
+                        .entry(*slot)
+                        .and_modify(|merged| merged.present_value = value.present_value)
+                        .or_insert_with(|| value.clone());
+                }
+            }
+            bundle.transactions.push((res, events));
+        }
+
+        // NOTE: the snapshot is discarded (rather than reverted to), retaining any taken before
+        self.snapshots.truncate(snapshot.0);
+        Ok(bundle)
+    }
+
+    /// Executes a block of transactions (as per [`Engine::execute_bundle`]) in the engine's block,
+    /// then credits the block reward and withdrawals, returning the receipts and state root
+    ///
+    /// NOTE: a block is atomic (i.e. as per a bundle), and each transaction cannot exceed the gas
+    /// remaining in the block. As per [`Engine::state_root`], the state root is that of the state
+    /// held in the engine, as are those of the receipts prior to Byzantium (see EIP-658).
+    pub fn execute_block(&mut self, block: Block) -> Result<ExecutedBlock, BlockError<DB::Error>> {
+        let snapshot = self.snapshot();
+        let executed = self.apply_block(block);
+        match executed {
+            Ok(_) => self.snapshots.truncate(snapshot.0),
+            Err(_) => {
+                self.revert_to(snapshot);
+            }
+        }
+        executed
+    }
+
+    fn apply_block(&mut self, block: Block) -> Result<ExecutedBlock, BlockError<DB::Error>> {
+        let tx_types: Vec<_> = block.transactions.iter().map(|tx| tx.tx_type).collect();
+        let gas_limit = self.block().gas_limit;
+
+        // NOTE: prior to Byzantium, receipts commit to the state root after each transaction -
+        // i.e. that before the next (or the block reward)
+        let roots = !self.spec().is_enabled_in(SpecId::BYZANTIUM);
+        let mut state_roots = Vec::new();
+        let bundle = self.execute_bundle_with(block.transactions, |engine, bundle, tx| {
+            if roots && !bundle.transactions.is_empty() {
+                state_roots.push(engine.state_root());
+            }
+            let gas_used: u64 = bundle
+                .transactions
+                .iter()
+                .map(|(res, _events)| res.result.gas_used())
+                .sum();
+            match tx.gas_limit > gas_limit.saturating_sub(gas_used) {
+                true => Err(InvalidTransaction::CallerGasLimitMoreThanBlock),
+                false => Ok(()),
+            }
+        })?;
+        if roots && !bundle.transactions.is_empty() {
+            state_roots.push(self.state_root());
+        }
+        let mut state_roots = state_roots.into_iter();
+
+        let beneficiary = self.block().beneficiary;
+        let reward = block.reward.unwrap_or_else(|| block::reward(self.spec()));
+        self.credit(beneficiary, reward)
+            .map_err(BlockError::Database)?;
+        for withdrawal in &block.withdrawals {
+            // NOTE: withdrawals are denominated in gwei
+            let amount = U256::from(withdrawal.amount) * U256::from(1_000_000_000);
+            self.credit(withdrawal.address, amount)
+                .map_err(BlockError::Database)?;
+        }
+
+        let mut cumulative_gas_used = 0;
+        let receipts = bundle
+            .transactions
+            .iter()
+            .zip(tx_types)
+            .map(|((res, _events), tx_type)| {
+                cumulative_gas_used += res.result.gas_used();
+                Receipt::new(
+                    tx_type,
+                    &res.result,
+                    cumulative_gas_used,
+                    state_roots.next(),
+                )
+            })
+            .collect();
+        Ok(ExecutedBlock::new(
+            bundle.transactions,
+            receipts,
+            self.state_root(),
+        ))
+    }
+
+    /// Credits an account's balance (e.g. with a block reward or withdrawal)
+    fn credit(&mut self, address: Address, amount: U256) -> Result<(), DB::Error> {
+        // NOTE: crediting nothing does not touch (i.e. create) the account
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let account = self.db_mut().load_account(address)?;
+        account.info.balance = account.info.balance.saturating_add(amount);
+        if account.account_state == AccountState::NotExisting {
+            account.account_state = AccountState::Touched;
+        }
+        Ok(())
+    }
+
+    /// Decodes a signed raw transaction (i.e. legacy or EIP-2718 typed) and executes it (as per
+    /// [`Engine::execute`]) from the sender recovered from its signature - see [`raw::decode`]
+    pub fn execute_raw(
+        &mut self,
+        raw: &[u8],
+    ) -> Result<(ResultAndState, Vec<Event>), raw::ExecuteError<DB::Error>> {
+        let tx = raw::decode(raw)?;
+        Ok(self.execute(tx)?)
+    }
+
+    /// Estimates the gas of a transaction - i.e. the minimal gas limit with which it succeeds (as per
+    /// `eth_estimateGas`), binary searching up to the transaction's gas limit
+    ///
+    /// NOTE: the gas used by a transaction is not a sufficient gas limit, as refunds are only given
+    /// once it completes, and calls are only given 63/64 of the gas remaining - see EIP-150. The
+    /// state changes of the transaction are not committed.
+    pub fn estimate_gas(&mut self, tx: TxEnv) -> Result<GasEstimate, EstimateError<DB::Error>> {
+        let (result, events) = self.execute(tx.clone())?;
+        if !result.result.is_success() {
+            return Err(EstimateError::Failed(result.result));
+        }
+
+        // NOTE: as per geth, the transaction cannot succeed with less than the gas it used, and
+        // likely succeeds with the gas it spent (i.e. before refunds) as of the 63/64 rule
+        let gas_used = result.result.gas_used();
+        let gas_refunded = match &result.result {
+            ExecutionResult::Success { gas_refunded, .. } => *gas_refunded,
+            _ => 0,
+        };
+        let mut estimate = GasEstimate {
+            gas_limit: tx.gas_limit,
+            result,
+            events,
+        };
+        let mut lo = gas_used.saturating_sub(1);
+        let optimistic = (gas_used + gas_refunded + interpreter::gas::CALL_STIPEND) * 64 / 63;
+
+        let mut mid = optimistic.min(estimate.gas_limit);
+        while lo + 1 < estimate.gas_limit {
+            let tx = TxEnv {
+                gas_limit: mid,
+                ..tx.clone()
+            };
+            match self.execute(tx) {
+                Ok((result, events)) if result.result.is_success() => {
+                    estimate = GasEstimate {
+                        gas_limit: mid,
+                        result,
+                        events,
+                    };
+                }
+                Ok(_) | Err(EVMError::Transaction(_)) => lo = mid,
+                Err(err) => return Err(err.into()),
+            }
+            mid = lo + (estimate.gas_limit - lo) / 2;
+        }
+
+        Ok(estimate)
+    }
+
+    /// Creates the access list of a transaction (as per `eth_createAccessList`) - i.e. the accounts
+    /// and storage slots accessed by its operations, as tracked by the tracer - and reports the gas
+    /// saved by it (i.e. relative to the transaction as provided)
+    ///
+    /// As the access list may change the path of the transaction (i.e. its gas), the transaction is
+    /// executed with it until it no longer changes. Legacy transactions are upgraded to EIP-2930
+    /// transactions (i.e. to carry the list), which requires Berlin - i.e. access lists cannot be
+    /// created prior to it. The state changes of the transaction are not committed.
+    pub fn create_access_list(
+        &mut self,
+        mut tx: TxEnv,
+    ) -> Result<CreatedAccessList, EVMError<DB::Error>> {
+        if !self.spec().is_enabled_in(SpecId::BERLIN) {
+            return Err(EVMError::Custom(format!(
+                "access lists require Berlin (see EIP-2930), not {}",
+                self.spec()
+            )));
+        }
+        // NOTE: EIP-2930 transactions require a chain ID, so the engine's is adopted by legacy
+        // transactions without one (i.e. prior to EIP-155)
+        if tx.tx_type == TransactionType::Legacy as u8 {
+            tx.tx_type = TransactionType::Eip2930 as u8;
+            tx.chain_id.get_or_insert(self.evm.data.ctx.cfg.chain_id);
+        }
+
+        // NOTE: as per geth, the sender, recipient and precompiles are excluded as they are
+        // warm regardless, although the slots of the recipient are not
+        let precompiles = Precompiles::new(self.spec().into());
+        let to = match tx.kind {
+            TxKind::Call(to) => to,
+            TxKind::Create => tx.caller.create(tx.nonce),
+        };
+        let caller = tx.caller;
+        let excluded = |address: &Address| {
+            *address == caller || *address == to || precompiles.contains(address)
+        };
+
+        let (mut result, mut events) = self.execute_tracking_accesses(tx.clone())?;
+        let gas_used = result.result.gas_used();
+        loop {
+            let mut accesses = std::mem::take(&mut self.evm.inspector().accesses);
+            for item in tx.access_list.iter() {
+                accesses
+                    .entry(item.address)
+                    .or_default()
+                    .extend(&item.storage_keys);
+            }
+            let access_list = AccessList(
+                accesses
+                    .into_iter()
+                    .filter(|(address, slots)| !slots.is_empty() || !excluded(address))
+                    .map(|(address, slots)| AccessListItem {
+                        address,
+                        storage_keys: slots.into_iter().collect(),
+                    })
+                    .collect(),
+            );
+            if access_list == tx.access_list {
+                break;
+            }
+
+            tx.access_list = access_list;
+            (result, events) = self.execute_tracking_accesses(tx.clone())?;
+        }
+
+        Ok(CreatedAccessList {
+            access_list: tx.access_list,
+            gas_used: result.result.gas_used(),
+            gas_saved: gas_used as i64 - result.result.gas_used() as i64,
+            result,
+            events,
+        })
+    }
+
+    /// Executes a transaction (as per [`Engine::execute`]), tracking the accounts and storage
+    /// slots accessed by its operations - see [`Engine::create_access_list`]
+    fn execute_tracking_accesses(
+        &mut self,
+        tx: TxEnv,
+    ) -> Result<(ResultAndState, Vec<Event>), EVMError<DB::Error>> {
+        self.evm.inspector().track_accesses = true;
+        let res = self.execute(tx);
+        self.evm.inspector().track_accesses = false;
+        res
+    }
+
+    /// Executes a transaction (as per [`Engine::execute`]) and commits the resulting state, such
+    /// that subsequent executions start from it
+    ///
+    /// NOTE: as of Spurious Dragon (i.e. EIP-161), empty accounts touched by the transaction (e.g. a
+    /// coinbase receiving no fees) are deleted, rather than committed.
+    pub fn execute_and_commit(
+        &mut self,
+        tx: TxEnv,
+    ) -> Result<(ResultAndState, Vec<Event>), EVMError<DB::Error>> {
+        let (res, events) = self.execute(tx)?;
+
+        let mut state = res.state.clone();
+        // NOTE: touched empty accounts are deleted as of Spurious Dragon - see EIP-161
+        if self.spec().is_enabled_in(SpecId::SPURIOUS_DRAGON) {
+            state
+                .values_mut()
+                .filter(|account| account.is_touched() && account.is_empty())
+                .for_each(Account::mark_selfdestruct);
+        }
+        self.evm.data.ctx.db().commit(state);
+
+        Ok((res, events))
+    }
+}
+
+/// Options for the trace captured by the [`Engine`] - modelled on geth's `TraceConfig`
+///
+/// By default, the stack and memory are captured for each step, whereas return data and storage
+/// accesses are not (and the number of steps is unlimited).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TraceConfig {
+    /// Omits the stack from each step
+    pub disable_stack: bool,
+    /// Omits the memory from each step
+    pub disable_memory: bool,
+    /// Includes the return data (of the last call) in each step
+    pub enable_return_data: bool,
+    /// Emits an [`Event::Storage`] for each storage access
+    pub enable_storage: bool,
+    /// Labels the stack inputs consumed by each step (see [`opcodes::inputs`])
+    pub enable_stack_inputs: bool,
+    /// Maximum number of steps to capture per transaction (subsequent steps are omitted)
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -110,8 +777,14 @@ struct StepPre {
     pc: usize,
     op: u8,
     gas: u64,
-    stack: Box<[U256]>,
+    refund: u64,
+    mem_size: usize,
+    recorded: bool,
+    stack: Option<Box<[U256]>>,
+    stack_inputs: Option<Box<[StackInput]>>,
     memory: Option<String>,
+    return_data: Option<Bytes>,
+    storage: Option<Storage>,
 }
 
 /// A single step of the EVM engine - inspired by <https://eips.ethereum.org/EIPS/eip-3155>
@@ -122,160 +795,875 @@ struct StepPre {
 /// {
 ///   "pc": 0,
 ///   "op": 96,
+///   "opName": "PUSH1",
 ///   "gas": 2250,
 ///   "gasCost": 3,
 ///   "stack": [],
-///   "depth": 1
+///   "depth": 1,
+///   "refund": 0,
+///   "memSize": 0
 /// }
 /// ```
-#[derive(Debug, Default, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Step {
     /// Program Counter
     pc: usize,
     /// OpCode
     op: u8,
+    /// Name of the operation (i.e. mnemonic)
+    op_name: &'static str,
     /// Gas left before executing this operation
     gas: u64,
     /// Gas cost of this operation
     gas_cost: u64,
     /// Array of all values on the stack
-    stack: Box<[U256]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stack: Option<Box<[U256]>>,
+    /// Labelled stack inputs consumed by this operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stack_inputs: Option<Box<[StackInput]>>,
     /// Depth of the call stack
     depth: u64,
+    /// Amount of global gas refunded before executing this operation
+    refund: u64,
     /// Description of an error (should contain revert reason if supported)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     error: Option<String>,
     /// Hex-String representation of all allocated values in memory
     #[serde(default, skip_serializing_if = "Option::is_none")]
     memory: Option<String>,
+    /// Size of memory (in bytes)
+    mem_size: usize,
+    /// Data returned by the last call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    return_data: Option<Bytes>,
 }
 
-/// Tracing events captured during EVM execution
-#[derive(Debug, PartialEq, Serialize)]
-#[serde(tag = "type")]
-pub enum Event {
-    /// A single step of the EVM engine
-    #[serde(rename = "step")]
-    Step(Step),
+/// A (labelled) stack input consumed by a [`Step`]
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "name": "key",
+///   "value": "0x1"
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackInput {
+    /// Name of the input (e.g. `key` for `SSTORE`)
+    name: &'static str,
+    /// Value of the input
+    value: U256,
 }
 
-struct Tracer {
-    gas_inspector: GasInspector,
-    step: Option<StepPre>,
-    events: Vec<Event>,
+/// A log emitted by one of the `LOG0`-`LOG4` operations
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "address": "0xffffffffffffffffffffffffffffffffffffffff",
+///   "topics": ["0x00000000000000000000000000000000000000000000000000000000000000aa"],
+///   "data": "0x2a",
+///   "depth": 1,
+///   "step": 6
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+    /// Address of the contract that emitted the log
+    address: Address,
+    /// Array of all (indexed) topics of the log
+    topics: Box<[B256]>,
+    /// Non-indexed data of the log
+    data: Bytes,
+    /// Depth of the call stack
+    depth: u64,
+    /// Index of the step (in execution order) that emitted the log
+    step: usize,
 }
 
-impl Tracer {
-    fn new() -> Self {
-        Self {
-            gas_inspector: GasInspector::new(),
-            step: None,
-            events: Default::default(),
+/// Scheme (i.e. opcode) used to enter a call frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CallScheme {
+    /// `CALL` (or the transaction itself)
+    #[default]
+    Call,
+    /// `CALLCODE`
+    CallCode,
+    /// `DELEGATECALL`
+    DelegateCall,
+    /// `STATICCALL`
+    StaticCall,
+    /// `EXTCALL` (EOF)
+    ExtCall,
+    /// `EXTSTATICCALL` (EOF)
+    ExtStaticCall,
+    /// `EXTDELEGATECALL` (EOF)
+    ExtDelegateCall,
+}
+
+impl From<interpreter::CallScheme> for CallScheme {
+    fn from(scheme: interpreter::CallScheme) -> Self {
+        match scheme {
+            interpreter::CallScheme::Call => Self::Call,
+            interpreter::CallScheme::CallCode => Self::CallCode,
+            interpreter::CallScheme::DelegateCall => Self::DelegateCall,
+            interpreter::CallScheme::StaticCall => Self::StaticCall,
+            interpreter::CallScheme::ExtCall => Self::ExtCall,
+            interpreter::CallScheme::ExtStaticCall => Self::ExtStaticCall,
+            interpreter::CallScheme::ExtDelegateCall => Self::ExtDelegateCall,
         }
     }
 }
 
-impl revm::Inspector<Context> for Tracer {
-    fn initialize_interp(&mut self, interpreter: &mut Interpreter, _ctx: &mut Context) {
-        self.gas_inspector
-            .initialize_interp(interpreter.control.gas());
-    }
+/// Entry into a call frame - emitted before the first step of the callee
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "scheme": "STATICCALL",
+///   "from": "0xffffffffffffffffffffffffffffffffffffffff",
+///   "to": "0x00000000000000000000000000000000000000ff",
+///   "value": "0x0",
+///   "input": "0x",
+///   "gas": 16470046,
+///   "depth": 2
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallEnter {
+    /// Scheme used to enter the call frame
+    scheme: CallScheme,
+    /// Address of the calling account
+    from: Address,
+    /// Address of the account whose code is executed
+    to: Address,
+    /// Value transferred (or apparent value for `DELEGATECALL`)
+    value: U256,
+    /// Call data passed to the callee
+    input: Bytes,
+    /// Gas given to the callee
+    gas: u64,
+    /// Depth of the call stack (within the call frame)
+    depth: u64,
+}
 
-    fn step(&mut self, interpreter: &mut Interpreter, _ctx: &mut Context) {
-        self.gas_inspector.step(interpreter.control.gas());
+/// Exit from a call frame - emitted after the last step of the callee
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "gasUsed": 2600,
+///   "output": "0x",
+///   "success": true,
+///   "depth": 2
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallExit {
+    /// Gas used by the callee
+    gas_used: u64,
+    /// Data returned (or reverted with) by the callee
+    output: Bytes,
+    /// Whether the call frame completed successfully (i.e. did not revert or halt)
+    success: bool,
+    /// Depth of the call stack (within the call frame)
+    depth: u64,
+}
 
-        let pc = interpreter.bytecode.pc();
-        let opcode = interpreter.bytecode.opcode();
-        let stack = interpreter.stack.data();
-        let gas_remaining = interpreter.control.gas().remaining();
+/// Scheme (i.e. opcode) used to enter a contract creation frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CreateScheme {
+    /// `CREATE` (or the transaction itself)
+    #[default]
+    Create,
+    /// `CREATE2`
+    Create2,
+    /// `EOFCREATE` (or the EOF transaction itself)
+    EofCreate,
+}
 
-        assert_eq!(self.step, None, "Should be empty - consumed by `step_end`");
-        self.step = Some(StepPre {
-            pc,
-            op: opcode,
-            stack: stack.clone().into_boxed_slice(),
-            gas: gas_remaining,
-            memory: if interpreter.memory.size() == 0 {
-                None
-            } else {
-                // TODO(toms): encode as base64 instead? (to save space)
-                Some(hex::encode_prefixed(
-                    interpreter
-                        .memory
-                        .slice(0..interpreter.memory.size())
-                        .as_ref(),
-                ))
-            },
-        });
+impl From<context_interface::CreateScheme> for CreateScheme {
+    fn from(scheme: context_interface::CreateScheme) -> Self {
+        match scheme {
+            context_interface::CreateScheme::Create => Self::Create,
+            context_interface::CreateScheme::Create2 { .. } => Self::Create2,
+        }
     }
+}
 
-    fn step_end(&mut self, interpreter: &mut Interpreter, ctx: &mut Context) {
-        self.gas_inspector.step_end(interpreter.control.gas_mut());
-
-        let step = self.step.take().unwrap();
-
-        self.events.push(Event::Step(Step {
-            pc: step.pc,
-            op: step.op,
-            stack: step.stack,
-            gas: step.gas,
-            gas_cost: self.gas_inspector.last_gas_cost(),
-            depth: ctx.journal().depth() as u64,
-            error: {
-                let result = interpreter.control.instruction_result();
-                (result.is_error() || result.is_revert()).then(|| format!("{:?}", result))
-            },
-            memory: step.memory,
-        }));
-    }
+/// Entry into a contract creation frame - emitted before the first step of the init code
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "scheme": "CREATE2",
+///   "creator": "0xffffffffffffffffffffffffffffffffffffffff",
+///   "salt": "0x0",
+///   "initCodeHash": "0x17c3092b6f26b342c59b9f80e8d3d8f3ae124106cbd6c0c7f36e6f7951ee0c12",
+///   "value": "0x0",
+///   "gas": 29479001,
+///   "depth": 2
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateEnter {
+    /// Scheme used to enter the contract creation frame
+    scheme: CreateScheme,
+    /// Address of the creating account
+    creator: Address,
+    /// Salt used to derive the address of the created contract (`CREATE2` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<U256>,
+    /// Keccak-256 hash of the init code
+    init_code_hash: B256,
+    /// Value transferred to the created contract
+    value: U256,
+    /// Gas given to the init code
+    gas: u64,
+    /// Depth of the call stack (within the contract creation frame)
+    depth: u64,
+}
 
-    fn log(&mut self, _interpreter: &mut Interpreter, _ctx: &mut Context, _log: Log) {}
+/// Exit from a contract creation frame - emitted after the last step of the init code
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "address": "0x2f2d12af7f357a9d2f36d276da056a7c2272decb",
+///   "codeSize": 0,
+///   "gasUsed": 4,
+///   "success": true,
+///   "depth": 2
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateExit {
+    /// Address of the created contract
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<Address>,
+    /// Size of the deployed (runtime) code
+    code_size: usize,
+    /// Gas used by the init code (and code deposit)
+    gas_used: u64,
+    /// Whether the contract was deployed successfully (i.e. did not revert or halt)
+    success: bool,
+    /// Depth of the call stack (within the contract creation frame)
+    depth: u64,
+}
 
-    fn call(&mut self, _ctx: &mut Context, _inputs: &mut CallInputs) -> Option<CallOutcome> {
-        None
-    }
+/// A self-destruct (i.e. `SELFDESTRUCT`) of a contract, transferring its balance to a beneficiary
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "contract": "0xffffffffffffffffffffffffffffffffffffffff",
+///   "beneficiary": "0x0000000000000000000000000000000000000000",
+///   "value": "0x2a",
+///   "destroyed": false,
+///   "depth": 1
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfDestruct {
+    /// Address of the self-destructed contract
+    contract: Address,
+    /// Address of the account receiving the balance of the contract
+    beneficiary: Address,
+    /// Balance transferred to the beneficiary
+    value: U256,
+    /// Whether the account is removed - since Cancun (i.e. EIP-6780), only if the contract was
+    /// created in the same transaction
+    destroyed: bool,
+    /// Depth of the call stack
+    depth: u64,
+}
+
+/// Kind of storage accessed by a storage operation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageKind {
+    /// Persistent storage (i.e. `SLOAD`/`SSTORE`)
+    #[default]
+    Persistent,
+    /// Transient storage (i.e. `TLOAD`/`TSTORE` - see EIP-1153)
+    Transient,
+}
+
+/// A storage access (i.e. `SLOAD`, `SSTORE`, `TLOAD` or `TSTORE`) of the preceding step
+///
+/// # Example (as serialized JSON)
+///
+/// ```json
+/// {
+///   "kind": "persistent",
+///   "address": "0xffffffffffffffffffffffffffffffffffffffff",
+///   "slot": "0x1",
+///   "oldValue": "0x0",
+///   "newValue": "0x2a",
+///   "cold": true,
+///   "depth": 1
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Storage {
+    /// Kind of storage accessed
+    kind: StorageKind,
+    /// Address of the account whose storage is accessed
+    address: Address,
+    /// Storage slot (i.e. key) accessed
+    slot: U256,
+    /// Value of the slot before the operation
+    old_value: U256,
+    /// Value of the slot after the operation (same as the old value for loads)
+    new_value: U256,
+    /// Whether the slot was cold (i.e. not yet accessed in the transaction - see EIP-2929)
+    cold: bool,
+    /// Depth of the call stack
+    depth: u64,
+}
+
+/// A call frame folded from the events of a trace - see [`CallFrame::tree`]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    /// Event that entered the call frame
+    enter: Event,
+    /// Event that exited the call frame (absent if the trace ends within the call frame)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exit: Option<Event>,
+    /// Nested call frames, in execution order
+    calls: Vec<CallFrame>,
+}
+
+impl CallFrame {
+    /// Folds the call frame events (i.e. [`Event::CallEnter`]/[`Event::CallExit`] and
+    /// [`Event::CreateEnter`]/[`Event::CreateExit`]) of a trace into a tree of (top-level) frames
+    pub fn tree(events: &[Event]) -> Vec<CallFrame> {
+        let mut roots = Vec::new();
+        let mut stack: Vec<CallFrame> = Vec::new();
+
+        let mut close = |stack: &mut Vec<CallFrame>, frame: CallFrame| match stack.last_mut() {
+            None => roots.push(frame),
+            Some(parent) => parent.calls.push(frame),
+        };
+
+        for event in events {
+            match event {
+                Event::CallEnter(_) | Event::CreateEnter(_) => stack.push(CallFrame {
+                    enter: event.clone(),
+                    exit: None,
+                    calls: Vec::new(),
+                }),
+                Event::CallExit(_) | Event::CreateExit(_) => {
+                    if let Some(mut frame) = stack.pop() {
+                        frame.exit = Some(event.clone());
+                        close(&mut stack, frame);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // NOTE: frames left open (i.e. truncated trace) are closed without an exit event
+        while let Some(frame) = stack.pop() {
+            close(&mut stack, frame);
+        }
+
+        roots
+    }
+
+    /// Event that entered the call frame
+    pub fn enter(&self) -> &Event {
+        &self.enter
+    }
+
+    /// Event that exited the call frame (absent if the trace ends within the call frame)
+    pub fn exit(&self) -> Option<&Event> {
+        self.exit.as_ref()
+    }
+
+    /// Nested call frames, in execution order
+    pub fn calls(&self) -> &[CallFrame] {
+        &self.calls
+    }
+}
+
+/// Tracing events captured during EVM execution
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    /// A single step of the EVM engine
+    #[serde(rename = "step")]
+    Step(Step),
+    /// A log emitted by the preceding step
+    #[serde(rename = "log")]
+    Log(Log),
+    /// Entry into a call frame
+    #[serde(rename = "callEnter")]
+    CallEnter(CallEnter),
+    /// Exit from a call frame
+    #[serde(rename = "callExit")]
+    CallExit(CallExit),
+    /// Entry into a contract creation frame
+    #[serde(rename = "createEnter")]
+    CreateEnter(CreateEnter),
+    /// Exit from a contract creation frame
+    #[serde(rename = "createExit")]
+    CreateExit(CreateExit),
+    /// A self-destruct of the contract executed by the preceding step
+    #[serde(rename = "selfDestruct")]
+    SelfDestruct(SelfDestruct),
+    /// A storage access by the preceding step
+    #[serde(rename = "storage")]
+    Storage(Storage),
+}
+
+#[derive(Debug, PartialEq)]
+struct SelfDestructPre {
+    destroyed: bool,
+    depth: u64,
+}
+
+struct Tracer {
+    config: TraceConfig,
+    gas_inspector: GasInspector,
+    step: Option<StepPre>,
+    steps: usize,
+    refunds: Vec<i64>,
+    logs: Vec<Log>,
+    selfdestruct: Option<SelfDestructPre>,
+    events: Vec<Event>,
+    /// Whether the accesses of the operations are tracked (i.e. only to create an access list)
+    track_accesses: bool,
+    /// Accounts (and storage slots) accessed by the operations of the transaction - see
+    /// [`Engine::create_access_list`]
+    accesses: BTreeMap<Address, BTreeSet<B256>>,
+}
+
+impl Tracer {
+    fn new(config: TraceConfig) -> Self {
+        Self {
+            config,
+            gas_inspector: GasInspector::new(),
+            step: None,
+            steps: 0,
+            refunds: Default::default(),
+            logs: Default::default(),
+            selfdestruct: None,
+            events: Default::default(),
+            track_accesses: false,
+            accesses: Default::default(),
+        }
+    }
+
+    /// Resets the state of the previous transaction (i.e. retaining the config), such that the
+    /// steps of each transaction are indexed (and limited) from zero
+    fn reset(&mut self) {
+        self.gas_inspector = GasInspector::new();
+        self.step = None;
+        self.steps = 0;
+        self.refunds.clear();
+        self.logs.clear();
+        self.selfdestruct = None;
+        self.events.clear();
+        self.accesses.clear();
+    }
+
+    /// Tracks the account (or storage slot) accessed by an operation (if any) - i.e. as per geth's
+    /// access list tracer
+    fn access(&mut self, op: u8, interpreter: &Interpreter) {
+        let stack = interpreter.stack.data();
+        let word = match op {
+            opcode::SLOAD | opcode::SSTORE => {
+                if let Some(slot) = stack.last() {
+                    self.accesses
+                        .entry(interpreter.input.target_address)
+                        .or_default()
+                        .insert(B256::from(*slot));
+                }
+                return;
+            }
+            opcode::BALANCE
+            | opcode::EXTCODESIZE
+            | opcode::EXTCODECOPY
+            | opcode::EXTCODEHASH
+            | opcode::SELFDESTRUCT => stack.last(),
+            opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL => {
+                stack.iter().nth_back(1)
+            }
+            _ => None,
+        };
+        if let Some(word) = word {
+            self.accesses
+                .entry(Address::from_word(B256::from(*word)))
+                .or_default();
+        }
+    }
+
+    fn storage<DB: DatabaseRef>(
+        op: u8,
+        interpreter: &Interpreter,
+        ctx: &mut Context<DB>,
+    ) -> Option<Storage> {
+        let kind = match op {
+            opcode::SLOAD | opcode::SSTORE => StorageKind::Persistent,
+            opcode::TLOAD | opcode::TSTORE => StorageKind::Transient,
+            _ => return None,
+        };
+
+        let stack = interpreter.stack.data();
+        let slot = *stack.last()?;
+        let address = interpreter.input.target_address;
+
+        let (old_value, cold) = match kind {
+            StorageKind::Persistent => match ctx
+                .journal()
+                .state
+                .get(&address)
+                .and_then(|account| account.storage.get(&slot))
+            {
+                Some(value) if !value.is_cold => (value.present_value, false),
+                // NOTE: resolved by `step_end` (once loaded by the operation)
+                _ => (U256::ZERO, true),
+            },
+            StorageKind::Transient => (
+                ctx.journal()
+                    .transient_storage
+                    .get(&(address, slot))
+                    .copied()
+                    .unwrap_or_default(),
+                false,
+            ),
+        };
+
+        let new_value = match op {
+            opcode::SSTORE | opcode::TSTORE => *stack.iter().nth_back(1)?,
+            _ => old_value,
+        };
+
+        Some(Storage {
+            kind,
+            address,
+            slot,
+            old_value,
+            new_value,
+            cold,
+            depth: 0,
+        })
+    }
+
+    fn create_exit<DB: DatabaseRef>(&mut self, ctx: &mut Context<DB>, outcome: &CreateOutcome) {
+        let success = outcome.instruction_result().is_ok();
+
+        self.events.push(Event::CreateExit(CreateExit {
+            address: outcome.address,
+            // NOTE: the output of successful init code is the deployed (runtime) code
+            code_size: if success { outcome.output().len() } else { 0 },
+            gas_used: outcome.gas().spent(),
+            success,
+            depth: ctx.journal().depth() as u64 + 1,
+        }));
+    }
+}
+
+impl<DB: DatabaseRef> revm::Inspector<Context<DB>> for Tracer {
+    fn initialize_interp(&mut self, interpreter: &mut Interpreter, _ctx: &mut Context<DB>) {
+        self.gas_inspector
+            .initialize_interp(interpreter.control.gas());
+    }
+
+    fn step(&mut self, interpreter: &mut Interpreter, ctx: &mut Context<DB>) {
+        self.gas_inspector.step(interpreter.control.gas());
+
+        let pc = interpreter.bytecode.pc();
+        let opcode = interpreter.bytecode.opcode();
+        let stack = interpreter.stack.data();
+        let gas_remaining = interpreter.control.gas().remaining();
+
+        // NOTE: accesses are tracked regardless of the config (i.e. even if not recorded)
+        if self.track_accesses {
+            self.access(opcode, interpreter);
+        }
+
+        // NOTE: refunds are accumulated per frame (and merged into the parent on success), so
+        // the global refund counter (i.e. geth's) is the sum over the frames of the call stack
+        let depth = ctx.journal().depth();
+        self.refunds.truncate(depth);
+        self.refunds.resize(depth, 0);
+        if let Some(refund) = self.refunds.last_mut() {
+            *refund = interpreter.control.gas().refunded();
+        }
+
+        // NOTE: steps beyond the limit are still tracked, but nothing is captured for them
+        let recorded = self.config.limit.is_none_or(|limit| self.steps < limit);
+
+        assert_eq!(self.step, None, "Should be empty - consumed by `step_end`");
+        self.step = Some(StepPre {
+            pc,
+            op: opcode,
+            gas: gas_remaining,
+            refund: self.refunds.iter().sum::<i64>().max(0) as u64,
+            mem_size: interpreter.memory.size(),
+            recorded,
+            stack: (recorded && !self.config.disable_stack)
+                .then(|| stack.clone().into_boxed_slice()),
+            stack_inputs: (recorded && self.config.enable_stack_inputs).then(|| {
+                opcodes::inputs(opcode)
+                    .iter()
+                    .zip(stack.iter().rev())
+                    .map(|(&name, &value)| StackInput { name, value })
+                    .collect()
+            }),
+            memory: if !recorded || self.config.disable_memory || interpreter.memory.size() == 0 {
+                None
+            } else {
+                // TODO(toms): encode as base64 instead? (to save space)
+                Some(hex::encode_prefixed(
+                    interpreter
+                        .memory
+                        .slice(0..interpreter.memory.size())
+                        .as_ref(),
+                ))
+            },
+            return_data: (recorded && self.config.enable_return_data)
+                .then(|| Bytes::copy_from_slice(interpreter.return_data.buffer())),
+            storage: if recorded && self.config.enable_storage {
+                Self::storage(opcode, interpreter, ctx)
+            } else {
+                None
+            },
+        });
+    }
+
+    fn step_end(&mut self, interpreter: &mut Interpreter, ctx: &mut Context<DB>) {
+        self.gas_inspector.step_end(interpreter.control.gas_mut());
+
+        let step = self.step.take().unwrap();
+
+        if step.recorded {
+            self.events.push(Event::Step(Step {
+                pc: step.pc,
+                op: step.op,
+                op_name: OpCode::name_by_op(step.op),
+                stack: step.stack,
+                stack_inputs: step.stack_inputs,
+                gas: step.gas,
+                gas_cost: self.gas_inspector.last_gas_cost(),
+                depth: ctx.journal().depth() as u64,
+                refund: step.refund,
+                error: {
+                    let result = interpreter.control.instruction_result();
+                    (result.is_error() || result.is_revert()).then(|| format!("{:?}", result))
+                },
+                memory: step.memory,
+                mem_size: step.mem_size,
+                return_data: step.return_data,
+            }));
+        }
+        self.steps += 1;
+
+        // NOTE: logs are reported (by `revm`) before `step_end`, so defer them until after
+        // the step that emitted them
+        self.events.extend(self.logs.drain(..).map(Event::Log));
+
+        if let Some(mut storage) = step.storage {
+            if interpreter.control.instruction_result().is_continue() {
+                // NOTE: cold slots are only loaded (from the database) by the operation
+                // itself, and must be unchanged (i.e. original) if not accessed before
+                if storage.cold {
+                    storage.old_value =
+                        ctx.journal().state[&storage.address].storage[&storage.slot].original_value;
+                    if matches!(step.op, opcode::SLOAD) {
+                        storage.new_value = storage.old_value;
+                    }
+                }
+                storage.depth = ctx.journal().depth() as u64;
+                self.events.push(Event::Storage(storage));
+            }
+        }
+
+        // NOTE: self-destructs are reported (by `revm`) after `step_end` without the context,
+        // so capture whether the account was destroyed (i.e. EIP-6780) from the journal here
+        if step.op == opcode::SELFDESTRUCT
+            && interpreter.control.instruction_result() == InstructionResult::SelfDestruct
+        {
+            let contract = interpreter.input.target_address;
+            let depth = ctx.journal().depth() as u64;
+            // NOTE: as of Cancun, a contract (not created in the same transaction) self-destructing
+            // to itself is a no-op, which `revm` does not journal (nor report) - i.e. it is
+            // reported here, as `revm` would otherwise (see `inspect_instructions`)
+            let reported = matches!(
+                ctx.journal().last_journal().last(),
+                Some(JournalEntry::AccountDestroyed { .. } | JournalEntry::BalanceTransfer { .. })
+            );
+            match reported {
+                true => {
+                    self.selfdestruct = Some(SelfDestructPre {
+                        destroyed: ctx
+                            .journal()
+                            .state
+                            .get(&contract)
+                            .is_some_and(|account| account.is_selfdestructed()),
+                        depth,
+                    })
+                }
+                false => self.events.push(Event::SelfDestruct(SelfDestruct {
+                    contract,
+                    beneficiary: contract,
+                    value: U256::ZERO,
+                    destroyed: false,
+                    depth,
+                })),
+            }
+        }
+    }
 
-    fn call_end(&mut self, _ctx: &mut Context, _inputs: &CallInputs, outcome: &mut CallOutcome) {
+    fn log(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        ctx: &mut Context<DB>,
+        log: revm::primitives::Log,
+    ) {
+        self.logs.push(Log {
+            address: log.address,
+            topics: log.data.topics().into(),
+            data: log.data.data,
+            depth: ctx.journal().depth() as u64,
+            step: self.steps,
+        });
+    }
+
+    fn call(&mut self, ctx: &mut Context<DB>, inputs: &mut CallInputs) -> Option<CallOutcome> {
+        // NOTE: the journal's depth is only incremented once the call frame is created
+        self.events.push(Event::CallEnter(CallEnter {
+            scheme: inputs.scheme.into(),
+            // NOTE: delegate calls preserve the caller (i.e. `msg.sender`) of the current
+            // call frame, so the calling contract is the (unchanged) target address
+            from: match inputs.scheme {
+                interpreter::CallScheme::DelegateCall
+                | interpreter::CallScheme::ExtDelegateCall => inputs.target_address,
+                _ => inputs.caller,
+            },
+            to: inputs.bytecode_address,
+            value: inputs.call_value(),
+            input: inputs.input.clone(),
+            gas: inputs.gas_limit,
+            depth: ctx.journal().depth() as u64 + 1,
+        }));
+        None
+    }
+
+    fn call_end(&mut self, ctx: &mut Context<DB>, _inputs: &CallInputs, outcome: &mut CallOutcome) {
         self.gas_inspector.call_end(outcome);
+
+        self.events.push(Event::CallExit(CallExit {
+            gas_used: outcome.gas().spent(),
+            output: outcome.output().clone(),
+            success: outcome.instruction_result().is_ok(),
+            depth: ctx.journal().depth() as u64 + 1,
+        }));
     }
 
-    fn create(&mut self, _ctx: &mut Context, _inputs: &mut CreateInputs) -> Option<CreateOutcome> {
+    fn create(
+        &mut self,
+        ctx: &mut Context<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.events.push(Event::CreateEnter(CreateEnter {
+            scheme: inputs.scheme.into(),
+            creator: inputs.caller,
+            salt: match inputs.scheme {
+                context_interface::CreateScheme::Create => None,
+                context_interface::CreateScheme::Create2 { salt } => Some(salt),
+            },
+            init_code_hash: keccak256(&inputs.init_code),
+            value: inputs.value,
+            gas: inputs.gas_limit,
+            depth: ctx.journal().depth() as u64 + 1,
+        }));
         None
     }
 
     fn create_end(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context<DB>,
         _inputs: &CreateInputs,
         outcome: &mut CreateOutcome,
     ) {
         self.gas_inspector.create_end(outcome);
+        self.create_exit(ctx, outcome);
     }
 
     fn eofcreate(
         &mut self,
-        _ctx: &mut Context,
-        _inputs: &mut EOFCreateInputs,
+        ctx: &mut Context<DB>,
+        inputs: &mut EOFCreateInputs,
     ) -> Option<CreateOutcome> {
+        self.events.push(Event::CreateEnter(CreateEnter {
+            scheme: CreateScheme::EofCreate,
+            creator: inputs.caller,
+            // NOTE: the salt of `EOFCREATE` is not exposed by `revm` (only the address)
+            salt: None,
+            init_code_hash: match &inputs.kind {
+                EOFCreateKind::Tx { initdata } => keccak256(initdata),
+                EOFCreateKind::Opcode { initcode, .. } => keccak256(initcode.raw()),
+            },
+            value: inputs.value,
+            gas: inputs.gas_limit,
+            depth: ctx.journal().depth() as u64 + 1,
+        }));
         None
     }
 
     fn eofcreate_end(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context<DB>,
         _inputs: &EOFCreateInputs,
-        _outcome: &mut CreateOutcome,
+        outcome: &mut CreateOutcome,
     ) {
+        self.gas_inspector.create_end(outcome);
+        self.create_exit(ctx, outcome);
     }
 
-    fn selfdestruct(&mut self, _contract: Address, _target: Address, _value: U256) {}
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        let SelfDestructPre { destroyed, depth } = self
+            .selfdestruct
+            .take()
+            .expect("Should be populated - by `step_end`");
+
+        self.events.push(Event::SelfDestruct(SelfDestruct {
+            contract,
+            beneficiary: target,
+            value,
+            destroyed,
+            depth,
+        }));
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use revm::context::result::HaltReason;
+    use revm::context::result::{HaltReason, InvalidTransaction};
     use revm::primitives::KECCAK_EMPTY;
     use revm::{
         bytecode::{Bytecode, opcode},
@@ -285,15 +1673,15 @@ mod tests {
         },
         context_interface::result::ExecutionResult,
         primitives::{Bytes, TxKind, address, hex::FromHex},
-        state::AccountInfo,
+        state::{AccountInfo, EvmStorageSlot},
     };
 
     macro_rules! assert_matches {
         ($lhs:expr, $rhs:pat $(,)?) => {{ assert!(matches!($lhs, $rhs), "match failed: {:?}", $lhs) }};
     }
 
-    fn stack(values: impl IntoIterator<Item = u64>) -> Box<[U256]> {
-        values.into_iter().map(U256::from).collect()
+    fn stack(values: impl IntoIterator<Item = u64>) -> Option<Box<[U256]>> {
+        Some(values.into_iter().map(U256::from).collect())
     }
 
     #[test]
@@ -349,9 +1737,17 @@ mod tests {
         let memory = "0x000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000004000000000000000000000000000000000000000000000000000000000000000";
 
         let expected = &[
+            Event::CallEnter(CallEnter {
+                scheme: CallScheme::Call,
+                to: address!("ffffffffffffffffffffffffffffffffffffffff"),
+                gas: 16756216,
+                depth: 1,
+                ..Default::default()
+            }),
             Event::Step(Step {
                 pc: 0,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16756216,
                 gas_cost: 3,
                 stack: stack([]),
@@ -361,6 +1757,7 @@ mod tests {
             Event::Step(Step {
                 pc: 2,
                 op: opcode::DUP1, // 128
+                op_name: "DUP1",
                 gas: 16756213,
                 gas_cost: 3,
                 stack: stack([64]),
@@ -370,6 +1767,7 @@ mod tests {
             Event::Step(Step {
                 pc: 3,
                 op: opcode::MSTORE8, // 83
+                op_name: "MSTORE8",
                 gas: 16756210,
                 gas_cost: 12,
                 stack: stack([64, 64]),
@@ -379,123 +1777,168 @@ mod tests {
             Event::Step(Step {
                 pc: 4,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16756198,
                 gas_cost: 3,
                 stack: stack([]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 6,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16756195,
                 gas_cost: 3,
                 stack: stack([64]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 8,
                 op: opcode::SSTORE, // 85
+                op_name: "SSTORE",
                 gas: 16756192,
                 gas_cost: 22100,
                 stack: stack([64, 64]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 9,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16734092,
                 gas_cost: 3,
                 stack: stack([]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 11,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16734089,
                 gas_cost: 3,
                 stack: stack([64]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 13,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16734086,
                 gas_cost: 3,
                 stack: stack([64, 0]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 15,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16734083,
                 gas_cost: 3,
                 stack: stack([64, 0, 64]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 17,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16734080,
                 gas_cost: 3,
                 stack: stack([64, 0, 64, 0]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 19,
                 op: opcode::GAS, // 90
+                op_name: "GAS",
                 gas: 16734077,
                 gas_cost: 2,
                 stack: stack([64, 0, 64, 0, 255]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 20,
                 op: opcode::STATICCALL, // 250
+                op_name: "STATICCALL",
                 gas: 16734075,
                 gas_cost: 16472646,
                 stack: stack([64, 0, 64, 0, 255, 16734075]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
+                ..Default::default()
+            }),
+            Event::CallEnter(CallEnter {
+                scheme: CallScheme::StaticCall,
+                from: address!("ffffffffffffffffffffffffffffffffffffffff"),
+                to: address!("00000000000000000000000000000000000000ff"),
+                input: [0; 64].into(),
+                gas: 16470046, // excludes cold account access cost (2600)
+                depth: 2,
+                ..Default::default()
+            }),
+            Event::CallExit(CallExit {
+                gas_used: 0,
+                success: true,
+                depth: 2,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 21,
                 op: opcode::PUSH1, // 96
+                op_name: "PUSH1",
                 gas: 16731475,
                 gas_cost: 3,
                 stack: stack([1]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
             Event::Step(Step {
                 pc: 23,
                 op: opcode::RETURN, // 243
+                op_name: "RETURN",
                 gas: 16731472,
                 gas_cost: 0,
                 stack: stack([1, 64]),
                 depth: 1,
                 memory: Some(memory.into()),
+                mem_size: 96,
                 ..Default::default()
             }),
+            Event::CallExit(CallExit {
+                gas_used: 0x60a8,
+                output: [0x40].into(),
+                success: true,
+                depth: 1,
+            }),
         ];
 
         let actual = events;
@@ -530,7 +1973,24 @@ mod tests {
             }
         );
 
-        assert_eq!(events, &[]);
+        assert_eq!(
+            events,
+            &[
+                Event::CallEnter(CallEnter {
+                    scheme: CallScheme::Call,
+                    to: address,
+                    gas: 29979000,
+                    depth: 1,
+                    ..Default::default()
+                }),
+                Event::CallExit(CallExit {
+                    gas_used: 0,
+                    success: true,
+                    depth: 1,
+                    ..Default::default()
+                }),
+            ]
+        );
     }
 
     #[test]
@@ -562,9 +2022,17 @@ mod tests {
         assert_eq!(
             events,
             &[
+                Event::CallEnter(CallEnter {
+                    scheme: CallScheme::Call,
+                    to: address,
+                    gas: 29979000,
+                    depth: 1,
+                    ..Default::default()
+                }),
                 Event::Step(Step {
                     pc: 0,
                     op: opcode::PUSH1, // 96
+                    op_name: "PUSH1",
                     stack: stack([]),
                     gas: 29979000,
                     gas_cost: 3,
@@ -574,12 +2042,19 @@ mod tests {
                 Event::Step(Step {
                     pc: 2,
                     op: opcode::STOP, // 0
+                    op_name: "STOP",
                     stack: stack([64]),
                     gas: 29978997,
                     gas_cost: 0,
                     depth: 1,
                     ..Default::default()
-                })
+                }),
+                Event::CallExit(CallExit {
+                    gas_used: 3,
+                    success: true,
+                    depth: 1,
+                    ..Default::default()
+                }),
             ]
         );
     }
@@ -613,9 +2088,17 @@ mod tests {
         assert_eq!(
             events,
             &[
+                Event::CallEnter(CallEnter {
+                    scheme: CallScheme::Call,
+                    to: address,
+                    gas: 29979000,
+                    depth: 1,
+                    ..Default::default()
+                }),
                 Event::Step(Step {
                     pc: 0,
                     op: opcode::PUSH0,
+                    op_name: "PUSH0",
                     stack: stack([]),
                     gas: 29979000,
                     gas_cost: 2,
@@ -625,16 +2108,136 @@ mod tests {
                 Event::Step(Step {
                     pc: 1,
                     op: opcode::SELFDESTRUCT,
+                    op_name: "SELFDESTRUCT",
                     stack: stack([0]),
                     gas: 29978998,
                     gas_cost: 5000,
                     depth: 1,
                     ..Default::default()
-                })
+                }),
+                Event::SelfDestruct(SelfDestruct {
+                    contract: address,
+                    beneficiary: Address::ZERO,
+                    value: U256::ZERO,
+                    destroyed: false, // only destroyed if created in the same transaction (EIP-6780)
+                    depth: 1,
+                }),
+                Event::CallExit(CallExit {
+                    gas_used: 5002,
+                    success: true,
+                    depth: 1,
+                    ..Default::default()
+                }),
             ]
         );
     }
 
+    #[test]
+    fn selfdestruct_to_self() {
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([opcode::ADDRESS, opcode::SELFDESTRUCT]));
+        let selfdestruct = |spec| {
+            let mut engine = Engine::new().with_spec(spec);
+            engine.create_account(
+                address,
+                AccountInfo::from_bytecode(bytecode.clone()).with_balance(U256::from(0x2a)),
+            );
+            let (res, events) = engine
+                .execute(TxEnv {
+                    kind: TxKind::Call(address),
+                    ..Default::default()
+                })
+                .unwrap();
+            // NOTE: the self-destruct is not left pending (i.e. for `Inspector::selfdestruct`)
+            assert_eq!(engine.evm.inspector().selfdestruct, None);
+            let events: Vec<_> = events
+                .into_iter()
+                .filter(|event| matches!(event, Event::SelfDestruct(_)))
+                .collect();
+            (res.state[&address].info.balance, events)
+        };
+
+        // As of Cancun, a contract self-destructing to itself is a no-op (i.e. keeps its balance),
+        // which `revm` does not report
+        assert_eq!(
+            selfdestruct(SpecId::CANCUN),
+            (
+                U256::from(0x2a),
+                vec![Event::SelfDestruct(SelfDestruct {
+                    contract: address,
+                    beneficiary: address,
+                    value: U256::ZERO,
+                    destroyed: false,
+                    depth: 1,
+                })]
+            )
+        );
+
+        // Prior to Cancun, the contract is destroyed (i.e. burning its balance)
+        assert_eq!(
+            selfdestruct(SpecId::SHANGHAI),
+            (
+                U256::ZERO,
+                vec![Event::SelfDestruct(SelfDestruct {
+                    contract: address,
+                    beneficiary: address,
+                    value: U256::from(0x2a),
+                    destroyed: true,
+                    depth: 1,
+                })]
+            )
+        );
+    }
+
+    #[test]
+    fn selfdestruct_created() {
+        let mut engine = Engine::new();
+
+        let caller = address!("00000000000000000000000000000000000000aa");
+        engine.create_account(caller, AccountInfo::from_balance(U256::from(0x2a)));
+
+        let (res, events) = engine
+            .execute(TxEnv {
+                caller,
+                kind: TxKind::Create,
+                value: U256::from(0x2a),
+                // ;; initialization code (self-destructs immediately)
+                data: [
+                    opcode::PUSH1, // `address`: account to send the current balance to
+                    0xbb,
+                    opcode::SELFDESTRUCT,
+                ]
+                .into(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // NOTE: `revm` reports the init code as returning (i.e. deploying empty code)
+        assert_matches!(
+            res.result,
+            ExecutionResult::Success {
+                reason: SuccessReason::Return,
+                ..
+            }
+        );
+
+        let address = caller.create(0);
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, Event::SelfDestruct(_)))
+                .collect::<Vec<_>>(),
+            [&Event::SelfDestruct(SelfDestruct {
+                contract: address,
+                beneficiary: address!("00000000000000000000000000000000000000bb"),
+                value: U256::from(0x2a),
+                destroyed: true, // created in the same transaction (EIP-6780)
+                depth: 1,
+            })]
+        );
+        assert!(res.state[&address].is_selfdestructed());
+    }
+
     #[test]
     fn underflow() {
         let mut engine = Engine::new();
@@ -660,16 +2263,32 @@ mod tests {
 
         assert_eq!(
             events,
-            &[Event::Step(Step {
-                pc: 0,
-                op: opcode::POP,
-                stack: stack([]),
-                gas: 29979000,
-                gas_cost: 2,
-                depth: 1,
-                error: Some("StackUnderflow".into()),
-                ..Default::default()
-            })]
+            &[
+                Event::CallEnter(CallEnter {
+                    scheme: CallScheme::Call,
+                    to: address,
+                    gas: 29979000,
+                    depth: 1,
+                    ..Default::default()
+                }),
+                Event::Step(Step {
+                    pc: 0,
+                    op: opcode::POP,
+                    op_name: "POP",
+                    stack: stack([]),
+                    gas: 29979000,
+                    gas_cost: 2,
+                    depth: 1,
+                    error: Some("StackUnderflow".into()),
+                    ..Default::default()
+                }),
+                Event::CallExit(CallExit {
+                    gas_used: 29979000,
+                    success: false,
+                    depth: 1,
+                    ..Default::default()
+                }),
+            ]
         );
     }
 
@@ -771,7 +2390,7 @@ mod tests {
         ]));
         engine.create_account(address, AccountInfo::from_bytecode(bytecode));
 
-        let (res, _events) = engine
+        let (res, events) = engine
             .execute(TxEnv {
                 kind: TxKind::Call(address),
                 data: [
@@ -805,6 +2424,99 @@ mod tests {
                 ]
             );
         }
+
+        let tree = CallFrame::tree(&events);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].calls().len(), 1);
+
+        let frame = &tree[0].calls()[0];
+        let Event::CreateEnter(enter) = frame.enter() else {
+            panic!("unexpected enter event: {:?}", frame.enter());
+        };
+        assert_eq!(enter.scheme, CreateScheme::Create2);
+        assert_eq!(enter.creator, address);
+        assert_eq!(enter.salt, Some(U256::ZERO));
+        assert_eq!(
+            enter.init_code_hash,
+            revm::primitives::keccak256([opcode::PUSH0, opcode::PUSH0, opcode::RETURN])
+        );
+        assert_eq!(enter.depth, 2);
+
+        let Some(Event::CreateExit(exit)) = frame.exit() else {
+            panic!("unexpected exit event: {:?}", frame.exit());
+        };
+        assert_eq!(
+            exit.address,
+            Some(address!("2f2d12af7f357a9d2f36d276da056a7c2272decb"))
+        );
+        assert_eq!(exit.code_size, 0);
+        assert!(exit.success);
+        assert_eq!(exit.depth, 2);
+    }
+
+    #[test]
+    fn create() {
+        let mut engine = Engine::new();
+
+        // pseudocode: deploy [0x2a] (as runtime code)
+        let init_code = Bytes::from([
+            opcode::PUSH1, // value
+            0x2a,
+            opcode::PUSH0, // offset
+            opcode::MSTORE8,
+            opcode::PUSH1, // size
+            0x01,
+            opcode::PUSH0, // offset
+            opcode::RETURN,
+        ]);
+
+        let (res, events) = engine
+            .execute(TxEnv {
+                kind: TxKind::Create,
+                data: init_code.clone(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let address = Address::ZERO.create(0);
+        assert_eq!(
+            res.result.output(),
+            Some(&Bytes::from([0x2a])),
+            "deployed code"
+        );
+        assert_eq!(
+            res.state[&address].info.code_hash,
+            revm::primitives::keccak256([0x2a])
+        );
+
+        assert_matches!(
+            events.first(),
+            Some(Event::CreateEnter(CreateEnter {
+                scheme: CreateScheme::Create,
+                creator: Address::ZERO,
+                salt: None,
+                depth: 1,
+                ..
+            }))
+        );
+        let Some(Event::CreateEnter(enter)) = events.first() else {
+            unreachable!()
+        };
+        assert_eq!(
+            enter.init_code_hash,
+            revm::primitives::keccak256(&init_code)
+        );
+
+        assert_eq!(
+            events.last(),
+            Some(&Event::CreateExit(CreateExit {
+                address: Some(address),
+                code_size: 1,
+                gas_used: 16 + 200, // includes code deposit cost (200 per byte)
+                success: true,
+                depth: 1,
+            }))
+        );
     }
 
     #[test]
@@ -841,4 +2553,1181 @@ mod tests {
 
         assert_eq!(res.state.len(), 2);
     }
+
+    #[test]
+    fn log() {
+        let mut engine = Engine::new();
+
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            // ;; store data in memory
+            opcode::PUSH1, // value
+            0x2a,
+            opcode::PUSH0, // offset
+            opcode::MSTORE8,
+            // ;; emit log
+            opcode::PUSH1, // `topic0`
+            0xaa,
+            opcode::PUSH1, // `size`: byte size to copy
+            0x01,
+            opcode::PUSH0, // `offset`: byte offset in the memory in bytes
+            opcode::LOG1,
+        ]));
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+        let (res, events) = engine
+            .execute(TxEnv {
+                kind: TxKind::Call(address),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_matches!(
+            res.result,
+            ExecutionResult::Success {
+                reason: SuccessReason::Stop,
+                ..
+            }
+        );
+        assert_eq!(res.result.logs().len(), 1);
+
+        // Log is reported immediately after the step (i.e. `LOG1`) that emitted it
+        assert_eq!(events.len(), 11);
+        assert_matches!(
+            &events[7],
+            Event::Step(Step {
+                op: opcode::LOG1,
+                op_name: "LOG1",
+                ..
+            })
+        );
+        assert_eq!(
+            events[8],
+            Event::Log(Log {
+                address,
+                topics: [B256::with_last_byte(0xaa)].into(),
+                data: [0x2a].into(),
+                depth: 1,
+                step: 6,
+            })
+        );
+        assert_matches!(
+            &events[9],
+            Event::Step(Step {
+                op: opcode::STOP,
+                op_name: "STOP",
+                ..
+            })
+        );
+
+        // Steps are indexed per transaction (i.e. not across the engine's executions)
+        let (_res, events) = engine
+            .execute(TxEnv {
+                kind: TxKind::Call(address),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_matches!(&events[8], Event::Log(Log { step: 6, .. }));
+    }
+
+    #[test]
+    fn call_tree() {
+        let mut engine = Engine::new();
+
+        // pseudocode: return [0x2a]
+        let callee = address!("00000000000000000000000000000000000000bb");
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            opcode::PUSH1, // value
+            0x2a,
+            opcode::PUSH0, // offset
+            opcode::MSTORE8,
+            opcode::PUSH1, // size
+            0x01,
+            opcode::PUSH0, // offset
+            opcode::RETURN,
+        ]));
+        engine.create_account(callee, AccountInfo::from_bytecode(bytecode));
+
+        // pseudocode: delegatecall(callee); call(callee)
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            opcode::PUSH0, // `retSize`
+            opcode::PUSH0, // `retOffset`
+            opcode::PUSH0, // `argsSize`
+            opcode::PUSH0, // `argsOffset`
+            opcode::PUSH1, // `address`
+            0xbb,
+            opcode::GAS, // `gas`
+            opcode::DELEGATECALL,
+            opcode::PUSH0, // `retSize`
+            opcode::PUSH0, // `retOffset`
+            opcode::PUSH0, // `argsSize`
+            opcode::PUSH0, // `argsOffset`
+            opcode::PUSH0, // `value`
+            opcode::PUSH1, // `address`
+            0xbb,
+            opcode::GAS, // `gas`
+            opcode::CALL,
+        ]));
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+        let (_res, events) = engine
+            .execute(TxEnv {
+                kind: TxKind::Call(address),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let tree = CallFrame::tree(&events);
+        assert_eq!(tree.len(), 1);
+
+        let root = &tree[0];
+        assert_matches!(
+            root.enter(),
+            Event::CallEnter(CallEnter {
+                scheme: CallScheme::Call,
+                depth: 1,
+                ..
+            })
+        );
+        assert_matches!(
+            root.exit(),
+            Some(Event::CallExit(CallExit {
+                success: true,
+                depth: 1,
+                ..
+            }))
+        );
+        assert_eq!(root.calls().len(), 2);
+
+        for (frame, scheme) in root
+            .calls()
+            .iter()
+            .zip([CallScheme::DelegateCall, CallScheme::Call])
+        {
+            let Event::CallEnter(enter) = frame.enter() else {
+                panic!("unexpected enter event: {:?}", frame.enter());
+            };
+            assert_eq!(enter.scheme, scheme);
+            assert_eq!(enter.from, address);
+            assert_eq!(enter.to, callee);
+            assert_eq!(enter.depth, 2);
+
+            let Some(Event::CallExit(exit)) = frame.exit() else {
+                panic!("unexpected exit event: {:?}", frame.exit());
+            };
+            assert_eq!(exit.output, Bytes::from([0x2a]));
+            assert!(exit.success);
+            assert_eq!(exit.depth, 2);
+
+            assert_eq!(frame.calls(), &[]);
+        }
+
+        // A truncated trace leaves the innermost frames without an exit
+        let truncated = CallFrame::tree(&events[..events.len() - 1]);
+        assert_eq!(truncated.len(), 1);
+        assert_eq!(truncated[0].exit(), None);
+        assert_eq!(truncated[0].calls(), root.calls());
+    }
+
+    #[test]
+    fn storage() {
+        let mut engine = Engine::new().with_trace_config(TraceConfig {
+            enable_storage: true,
+            ..Default::default()
+        });
+
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            // ;; persistent storage
+            opcode::PUSH1, // `value`
+            0x2a,
+            opcode::PUSH1, // `key`
+            0x01,
+            opcode::SSTORE,
+            opcode::PUSH1, // `key`
+            0x01,
+            opcode::SLOAD,
+            opcode::PUSH1, // `value`
+            0x2b,
+            opcode::PUSH1, // `key`
+            0x01,
+            opcode::SSTORE,
+            // ;; transient storage
+            opcode::PUSH1, // `value`
+            0x2c,
+            opcode::PUSH1, // `key`
+            0x03,
+            opcode::TSTORE,
+            opcode::PUSH1, // `key`
+            0x03,
+            opcode::TLOAD,
+        ]));
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+        let (_res, events) = engine
+            .execute(TxEnv {
+                kind: TxKind::Call(address),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let storage = |kind, slot: u64, old_value: u64, new_value: u64, cold| {
+            Event::Storage(Storage {
+                kind,
+                address,
+                slot: U256::from(slot),
+                old_value: U256::from(old_value),
+                new_value: U256::from(new_value),
+                cold,
+                depth: 1,
+            })
+        };
+
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, Event::Storage(_)))
+                .collect::<Vec<_>>(),
+            [
+                &storage(StorageKind::Persistent, 1, 0x00, 0x2a, true),
+                &storage(StorageKind::Persistent, 1, 0x2a, 0x2a, false),
+                &storage(StorageKind::Persistent, 1, 0x2a, 0x2b, false),
+                &storage(StorageKind::Transient, 3, 0x00, 0x2c, false),
+                &storage(StorageKind::Transient, 3, 0x2c, 0x2c, false),
+            ]
+        );
+
+        // Storage access is reported immediately after the step that accessed it
+        for (n, event) in events.iter().enumerate() {
+            if let Event::Storage(_) = event {
+                assert_matches!(
+                    &events[n - 1],
+                    Event::Step(Step {
+                        op: opcode::SLOAD | opcode::SSTORE | opcode::TLOAD | opcode::TSTORE,
+                        ..
+                    })
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn trace_config() {
+        fn execute(config: TraceConfig) -> Vec<Event> {
+            let mut engine = Engine::new().with_trace_config(config);
+
+            // pseudocode: return [0x2a]
+            let callee = address!("00000000000000000000000000000000000000bb");
+            let bytecode = Bytecode::new_raw(Bytes::from([
+                opcode::PUSH1, // value
+                0x2a,
+                opcode::PUSH0, // offset
+                opcode::MSTORE8,
+                opcode::PUSH1, // size
+                0x01,
+                opcode::PUSH0, // offset
+                opcode::RETURN,
+            ]));
+            engine.create_account(callee, AccountInfo::from_bytecode(bytecode));
+
+            // pseudocode: staticcall(callee); sstore(0, 0)
+            let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+            let bytecode = Bytecode::new_raw(Bytes::from([
+                opcode::PUSH0, // `retSize`
+                opcode::PUSH0, // `retOffset`
+                opcode::PUSH0, // `argsSize`
+                opcode::PUSH0, // `argsOffset`
+                opcode::PUSH1, // `address`
+                0xbb,
+                opcode::GAS, // `gas`
+                opcode::STATICCALL,
+                opcode::PUSH0, // `value`
+                opcode::PUSH0, // `key`
+                opcode::SSTORE,
+            ]));
+            engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+            let (_res, events) = engine
+                .execute(TxEnv {
+                    kind: TxKind::Call(address),
+                    ..Default::default()
+                })
+                .unwrap();
+            events
+        }
+
+        fn steps(events: &[Event]) -> Vec<&Step> {
+            events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Step(step) => Some(step),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        // By default, the stack and memory are captured (but not return data or storage)
+        let events = execute(TraceConfig::default());
+        assert_eq!(steps(&events).len(), 17);
+        assert!(steps(&events).iter().all(|step| step.stack.is_some()));
+        assert!(steps(&events).iter().any(|step| step.memory.is_some()));
+        assert!(steps(&events).iter().all(|step| step.return_data.is_none()));
+        assert!(
+            steps(&events)
+                .iter()
+                .all(|step| step.stack_inputs.is_none())
+        );
+        assert!(
+            !events
+                .iter()
+                .any(|event| matches!(event, Event::Storage(_)))
+        );
+
+        let events = execute(TraceConfig {
+            disable_stack: true,
+            disable_memory: true,
+            enable_return_data: true,
+            enable_storage: true,
+            enable_stack_inputs: true,
+            limit: None,
+        });
+        assert_eq!(steps(&events).len(), 17);
+        assert!(steps(&events).iter().all(|step| step.stack.is_none()));
+        assert!(steps(&events).iter().all(|step| step.memory.is_none()));
+        assert!(
+            steps(&events)
+                .iter()
+                .all(|step| step.stack_inputs.is_some())
+        );
+        assert_eq!(
+            steps(&events)
+                .iter()
+                .map(|step| step.return_data.as_ref().unwrap().len())
+                .collect::<Vec<_>>(),
+            [[0; 13].as_slice(), &[1; 4]].concat(),
+            "return data is only populated after the call"
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, Event::Storage(_)))
+                .count(),
+            1
+        );
+
+        // Steps beyond the limit are omitted, but other events are not
+        let events = execute(TraceConfig {
+            enable_storage: true,
+            limit: Some(3),
+            ..Default::default()
+        });
+        assert_eq!(steps(&events).len(), 3);
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, Event::CallEnter(_) | Event::CallExit(_)))
+                .count(),
+            4
+        );
+        assert!(
+            !events
+                .iter()
+                .any(|event| matches!(event, Event::Storage(_)))
+        );
+
+        // The limit applies to each transaction (i.e. not across the engine's executions)
+        let mut engine = Engine::new().with_trace_config(TraceConfig {
+            limit: Some(10),
+            ..Default::default()
+        });
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([opcode::PUSH0; 12]));
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+        for nonce in 0..2 {
+            let (_res, events) = engine
+                .execute_and_commit(TxEnv {
+                    kind: TxKind::Call(address),
+                    nonce,
+                    ..Default::default()
+                })
+                .unwrap();
+            assert_eq!(steps(&events).len(), 10);
+        }
+    }
+
+    #[test]
+    fn eip3155() {
+        let mut engine = Engine::new();
+
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            opcode::PUSH1, // `value`
+            0x2a,
+            opcode::PUSH1, // `key`
+            0x01,
+            opcode::SSTORE,
+            opcode::PUSH1, // `value`
+            0x00,
+            opcode::PUSH1, // `key`
+            0x01,
+            opcode::SSTORE, // refunds the (cleared) slot
+        ]));
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+        let (res, events) = engine
+            .execute_and_commit(TxEnv {
+                kind: TxKind::Call(address),
+                gas_limit: 0x1000000,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let mut output = Vec::new();
+        eip3155::write(
+            &mut output,
+            &events,
+            &eip3155::Summary::new(&res.result, engine.spec()).with_state_root(engine.state_root()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"{"pc":0,"op":96,"gas":"0xffadf8","gasCost":"0x3","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":2,"op":96,"gas":"0xffadf5","gasCost":"0x3","memSize":0,"stack":["0x2a"],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":4,"op":85,"gas":"0xffadf2","gasCost":"0x5654","memSize":0,"stack":["0x2a","0x1"],"depth":1,"refund":0,"opName":"SSTORE"}"#,
+                "\n",
+                r#"{"pc":5,"op":96,"gas":"0xff579e","gasCost":"0x3","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":7,"op":96,"gas":"0xff579b","gasCost":"0x3","memSize":0,"stack":["0x0"],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+                "\n",
+                r#"{"pc":9,"op":85,"gas":"0xff5798","gasCost":"0x64","memSize":0,"stack":["0x0","0x1"],"depth":1,"refund":0,"opName":"SSTORE"}"#,
+                "\n",
+                r#"{"pc":10,"op":0,"gas":"0xff5734","gasCost":"0x0","memSize":0,"stack":[],"depth":1,"refund":19900,"opName":"STOP"}"#,
+                "\n",
+                r#"{"stateRoot":"0x20d4be1c063a338351e73de5fd9d9d58ea6e5f15a221d224e2eab5ceb3e28b73","output":"0x","gasUsed":"0x870a","pass":true,"fork":"Prague"}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn stack_inputs() {
+        let mut engine = Engine::new().with_trace_config(TraceConfig {
+            enable_stack_inputs: true,
+            ..Default::default()
+        });
+
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            opcode::PUSH1, // `value`
+            0x2a,
+            opcode::PUSH1, // `key`
+            0x01,
+            opcode::SSTORE,
+        ]));
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+        let (_res, events) = engine
+            .execute(TxEnv {
+                kind: TxKind::Call(address),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let steps: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Step(step) => Some(step),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            steps
+                .iter()
+                .map(|step| (step.op_name, step.stack_inputs.as_deref().unwrap()))
+                .collect::<Vec<_>>(),
+            [
+                ("PUSH1", [].as_slice()),
+                ("PUSH1", &[]),
+                (
+                    "SSTORE",
+                    &[
+                        StackInput {
+                            name: "key",
+                            value: U256::from(0x01),
+                        },
+                        StackInput {
+                            name: "value",
+                            value: U256::from(0x2a),
+                        },
+                    ]
+                ),
+                ("STOP", &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn opcodes() {
+        for op in 0..=u8::MAX {
+            let inputs = opcodes::inputs(op);
+            if let Some(opcode) = OpCode::new(op) {
+                assert!(
+                    inputs.is_empty() || inputs.len() == opcode.inputs() as usize,
+                    "{opcode}: {inputs:?}"
+                );
+            } else {
+                assert!(inputs.is_empty(), "{op:#x}: {inputs:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn spec() {
+        fn execute(spec: SpecId) -> ExecutionResult {
+            let mut engine = Engine::new().with_spec(spec);
+            assert_eq!(engine.spec(), spec);
+
+            let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+            let bytecode = Bytecode::new_raw(Bytes::from([opcode::PUSH0]));
+            engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+            let (res, _events) = engine
+                .execute(TxEnv {
+                    kind: TxKind::Call(address),
+                    ..Default::default()
+                })
+                .unwrap();
+            res.result
+        }
+
+        assert_eq!(Engine::new().spec(), SpecId::PRAGUE);
+
+        // `PUSH0` was introduced by Shanghai (EIP-3855)
+        assert_matches!(
+            execute(SpecId::SHANGHAI),
+            ExecutionResult::Success {
+                reason: SuccessReason::Stop,
+                ..
+            }
+        );
+        assert_matches!(
+            execute(SpecId::MERGE),
+            ExecutionResult::Halt {
+                reason: HaltReason::NotActivated,
+                ..
+            }
+        );
+    }
+
+    #[test]
+    fn block() {
+        let block = BlockEnv {
+            number: 0x1234,
+            beneficiary: address!("00000000000000000000000000000000000000cb"),
+            timestamp: 0x6553f100,
+            gas_limit: 30_000_000,
+            basefee: 7,
+            prevrandao: Some(B256::with_last_byte(0x2a)),
+            ..Default::default()
+        };
+
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            opcode::NUMBER,
+            opcode::TIMESTAMP,
+            opcode::COINBASE,
+            opcode::BASEFEE,
+            opcode::DIFFICULTY, // i.e. `PREVRANDAO`
+            opcode::GASLIMIT,
+        ]));
+        let tx = TxEnv {
+            kind: TxKind::Call(address),
+            ..Default::default()
+        };
+
+        // The (zero) gas price of the transaction does not cover the base fee
+        let mut engine = Engine::new().with_block(block.clone());
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode.clone()));
+        assert_matches!(
+            engine.execute(tx.clone()),
+            Err(EVMError::Transaction(
+                InvalidTransaction::GasPriceLessThanBasefee
+            ))
+        );
+
+        let mut engine = Engine::new().with_block(block).with_base_fee_check(false);
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+        let (_res, events) = engine.execute(tx).unwrap();
+        let Some(Event::Step(step)) = events.iter().rev().nth(1) else {
+            panic!("expected (final) step: {events:?}");
+        };
+        assert_eq!(step.op, opcode::STOP);
+        assert_eq!(
+            step.stack,
+            stack([0x1234, 0x6553f100, 0xcb, 7, 0x2a, 30_000_000])
+        );
+    }
+
+    #[test]
+    fn commit() {
+        let mut engine = Engine::new();
+
+        // Runtime code increments (and returns) a counter at slot 0
+        let runtime = [
+            opcode::PUSH0, // `key`
+            opcode::SLOAD,
+            opcode::PUSH1, // `b`
+            0x01,
+            opcode::ADD,
+            opcode::DUP1,
+            opcode::PUSH0, // `key`
+            opcode::SSTORE,
+            opcode::PUSH0, // `offset`
+            opcode::MSTORE,
+            opcode::PUSH1, // `size`
+            0x20,
+            opcode::PUSH0, // `offset`
+            opcode::RETURN,
+        ];
+        let init_code = [
+            [
+                opcode::PUSH1, // `size`
+                runtime.len() as u8,
+                opcode::DUP1,
+                opcode::PUSH1, // `offset`
+                0x09,
+                opcode::PUSH0, // `destOffset`
+                opcode::CODECOPY,
+                opcode::PUSH0, // `offset`
+                opcode::RETURN,
+            ]
+            .as_slice(),
+            &runtime,
+        ]
+        .concat();
+
+        let (res, _events) = engine
+            .execute_and_commit(TxEnv {
+                kind: TxKind::Create,
+                data: init_code.into(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let ExecutionResult::Success {
+            output: Output::Create(code, Some(address)),
+            ..
+        } = res.result
+        else {
+            panic!("expected (successful) create: {:?}", res.result);
+        };
+        assert_eq!(address, Address::ZERO.create(0));
+        assert_eq!(code, Bytes::from(runtime));
+
+        let mut call = |nonce: u64, commit: bool| {
+            let tx = TxEnv {
+                kind: TxKind::Call(address),
+                nonce,
+                ..Default::default()
+            };
+            let (res, _events) = if commit {
+                engine.execute_and_commit(tx)
+            } else {
+                engine.execute(tx)
+            }
+            .unwrap();
+            U256::from_be_slice(res.result.output().unwrap())
+        };
+
+        assert_eq!(call(1, true), U256::from(1));
+        // Without committing, the state (including the nonce of the caller) is unchanged
+        assert_eq!(call(2, false), U256::from(2));
+        assert_eq!(call(2, true), U256::from(2));
+        assert_eq!(call(3, true), U256::from(3));
+    }
+
+    #[test]
+    fn commit_touched_empty() {
+        // The (empty) recipient is touched by the transaction
+        let recipient = address!("00000000000000000000000000000000000000ee");
+        let execute = |spec: SpecId| {
+            let mut engine = Engine::new().with_spec(spec);
+            engine
+                .execute_and_commit(TxEnv {
+                    kind: TxKind::Call(recipient),
+                    ..Default::default()
+                })
+                .unwrap();
+            engine.dump_alloc()
+        };
+
+        // Prior to Spurious Dragon (i.e. EIP-161), touched empty accounts are committed
+        let alloc = execute(SpecId::TANGERINE);
+        assert_eq!(alloc.get(&recipient), Some(&GenesisAccount::default()));
+        assert!(alloc.contains_key(&Address::ZERO));
+
+        let alloc = execute(SpecId::SPURIOUS_DRAGON);
+        assert_eq!(alloc.get(&recipient), None);
+        // NOTE: the caller is not empty (i.e. its nonce is incremented)
+        assert_eq!(alloc.keys().collect::<Vec<_>>(), [&Address::ZERO]);
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut engine = Engine::new();
+
+        // Increments (and returns) a counter at slot 0
+        let address = address!("ffffffffffffffffffffffffffffffffffffffff");
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            opcode::PUSH0, // `key`
+            opcode::SLOAD,
+            opcode::PUSH1, // `b`
+            0x01,
+            opcode::ADD,
+            opcode::DUP1,
+            opcode::PUSH0, // `key`
+            opcode::SSTORE,
+            opcode::PUSH0, // `offset`
+            opcode::MSTORE,
+            opcode::PUSH1, // `size`
+            0x20,
+            opcode::PUSH0, // `offset`
+            opcode::RETURN,
+        ]));
+        engine.create_account(address, AccountInfo::from_bytecode(bytecode));
+
+        let call = |engine: &mut Engine, nonce: u64| {
+            let (res, _events) = engine
+                .execute_and_commit(TxEnv {
+                    kind: TxKind::Call(address),
+                    nonce,
+                    ..Default::default()
+                })
+                .unwrap();
+            U256::from_be_slice(res.result.output().unwrap())
+        };
+
+        let setup = engine.snapshot();
+        assert_eq!(call(&mut engine, 0), U256::from(1));
+
+        let nested = engine.snapshot();
+        assert_eq!(call(&mut engine, 1), U256::from(2));
+
+        assert!(engine.revert_to(nested));
+        assert_eq!(call(&mut engine, 1), U256::from(2));
+
+        // Reverting to the setup state also discards the (later) nested snapshot
+        assert!(engine.revert_to(setup));
+        assert!(!engine.revert_to(nested));
+        assert!(!engine.revert_to(setup));
+        assert_eq!(call(&mut engine, 0), U256::from(1));
+    }
+
+    #[test]
+    fn bundle() {
+        let mut engine = Engine::new();
+
+        // Increments (and returns) a counter at slot 0
+        let counter = address!("00000000000000000000000000000000000000aa");
+        engine.create_account(
+            counter,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&hex!(
+                "5f546001018060005560005260205ff3"
+            )))),
+        );
+        let caller = address!("00000000000000000000000000000000000000cc");
+        let tx = |nonce: u64| TxEnv {
+            caller,
+            kind: TxKind::Call(counter),
+            nonce,
+            ..Default::default()
+        };
+
+        // Each transaction sees the state committed by those before it
+        let bundle = engine.execute_bundle([tx(0), tx(1)]).unwrap();
+        let outputs: Vec<_> = bundle
+            .transactions
+            .iter()
+            .map(|(res, _events)| U256::from_be_slice(res.result.output().unwrap()))
+            .collect();
+        assert_eq!(outputs, [U256::from(1), U256::from(2)]);
+        assert!(
+            bundle
+                .transactions
+                .iter()
+                .all(|(_res, events)| { matches!(events.first(), Some(Event::CallEnter(_))) })
+        );
+
+        // The cumulative state changes retain the original values (i.e. as of before the bundle)
+        assert_eq!(bundle.state[&caller].info.nonce, 2);
+        let slot = &bundle.state[&counter].storage[&U256::ZERO];
+        assert_eq!(
+            (slot.original_value, slot.present_value),
+            (U256::ZERO, U256::from(2))
+        );
+
+        // An invalid transaction (i.e. a reused nonce) reverts the whole bundle
+        let snapshot = engine.snapshot();
+        assert_matches!(
+            engine.execute_bundle([tx(2), tx(2)]),
+            Err(BundleError {
+                index: 1,
+                error: EVMError::Transaction(InvalidTransaction::NonceTooLow { .. }),
+            })
+        );
+        assert_eq!(engine.dump_alloc()[&caller].nonce, 2);
+
+        // NOTE: snapshots taken before the bundle are retained
+        assert!(engine.revert_to(snapshot));
+
+        // Creates (with `CREATE2`) a contract storing 1 at slot 1, or self-destructing if called
+        // with data - i.e. prior to Cancun (EIP-6780), such that it is destroyed
+        let mut engine = Engine::new().with_spec(SpecId::SHANGHAI);
+        let init_code = hex!("600d8060095f395ff336600a576001600155005b33ff");
+        let factory = address!("00000000000000000000000000000000000000ff");
+        engine.create_account(
+            factory,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from(
+                [
+                    [opcode::PUSH22].as_slice(),
+                    &init_code,
+                    &hex!("5f525f6016600a5ff500"),
+                ]
+                .concat(),
+            ))),
+        );
+        let contract = factory.create2(B256::ZERO, revm::primitives::keccak256(init_code));
+        let call = |nonce: u64, to: Address, data: &'static [u8]| TxEnv {
+            kind: TxKind::Call(to),
+            data: Bytes::from_static(data),
+            ..tx(nonce)
+        };
+
+        // The storage written before the contract is self-destructed (and re-created) is cleared
+        let bundle = engine
+            .execute_bundle([
+                call(0, factory, &[]),
+                call(1, contract, &[]),
+                call(2, contract, &[0x01]),
+                call(3, factory, &[]),
+            ])
+            .unwrap();
+        assert!(
+            bundle
+                .transactions
+                .iter()
+                .all(|(res, _events)| res.result.is_success())
+        );
+        let account = &bundle.state[&contract];
+        assert!(account.is_created());
+        assert_eq!(account.storage.get(&U256::from(1)), None);
+        assert!(engine.dump_alloc()[&contract].storage.is_empty());
+    }
+
+    #[test]
+    fn estimate_gas() {
+        // NOTE: the step limit applies to each execution (i.e. not across the binary search)
+        let mut engine = Engine::new().with_trace_config(TraceConfig {
+            limit: Some(100),
+            ..Default::default()
+        });
+
+        let caller = address!("00000000000000000000000000000000000000cc");
+        let alloc: Alloc = serde_json::from_value(serde_json::json!({
+            // Calls 0xbb with all (i.e. 63/64 of the remaining) gas, reverting if the call fails
+            "0x00000000000000000000000000000000000000aa": {
+                "code": "0x5f5f5f5f5f7300000000000000000000000000000000000000bb5af115602157005b5f5ffd",
+            },
+            // Stores 1 at slot 0
+            "0x00000000000000000000000000000000000000bb": { "code": "0x60015f5500" },
+            // Clears slot 0 (i.e. refunding gas)
+            "0x00000000000000000000000000000000000000dd": {
+                "code": "0x5f5f5500",
+                "storage": { "0x00": "0x01" },
+            },
+            // Reverts
+            "0x00000000000000000000000000000000000000ee": { "code": "0x5f5ffd" },
+        }))
+        .unwrap();
+        engine.load_alloc(alloc);
+
+        let tx = |to| TxEnv {
+            caller,
+            kind: TxKind::Call(to),
+            gas_limit: 1_000_000,
+            ..Default::default()
+        };
+
+        for to in [
+            address!("00000000000000000000000000000000000000aa"),
+            address!("00000000000000000000000000000000000000dd"),
+        ] {
+            let estimate = engine.estimate_gas(tx(to)).unwrap();
+            assert!(estimate.result.result.is_success());
+            // The gas used (i.e. after refunds, and excluding the 1/64 withheld) is not sufficient
+            assert!(estimate.gas_limit > estimate.result.result.gas_used());
+            // The events are those of the execution with the estimate
+            assert!(
+                estimate
+                    .events
+                    .iter()
+                    .any(|event| matches!(event, Event::Step(_)))
+            );
+
+            // The estimate is the minimal gas limit with which the transaction succeeds
+            let run = |engine: &mut Engine, gas_limit| {
+                let tx = TxEnv {
+                    gas_limit,
+                    ..tx(to)
+                };
+                engine.execute(tx).unwrap().0.result.is_success()
+            };
+            assert!(run(&mut engine, estimate.gas_limit));
+            assert!(!run(&mut engine, estimate.gas_limit - 1));
+        }
+
+        // The estimate is not committed
+        assert_eq!(engine.dump_alloc().get(&caller), None);
+
+        // Transactions failing with their gas limit cannot be estimated
+        assert!(matches!(
+            engine.estimate_gas(tx(address!("00000000000000000000000000000000000000ee"))),
+            Err(EstimateError::Failed(ExecutionResult::Revert { .. }))
+        ));
+        assert!(matches!(
+            engine.estimate_gas(TxEnv {
+                gas_limit: 30_000,
+                ..tx(address!("00000000000000000000000000000000000000aa"))
+            }),
+            Err(EstimateError::Failed(ExecutionResult::Revert { .. }))
+        ));
+    }
+
+    #[test]
+    fn create_access_list() {
+        let mut engine = Engine::new();
+
+        let caller = address!("00000000000000000000000000000000000000dd");
+        let contract = address!("00000000000000000000000000000000000000aa");
+        let alloc: Alloc = serde_json::from_value(serde_json::json!({
+            // Loads slot 1, the balance of 0xbb and the code size of 0xcc, then calls the identity
+            // precompile (0x04)
+            "0x00000000000000000000000000000000000000aa": {
+                "code": concat!(
+                    "0x6001545073",
+                    "00000000000000000000000000000000000000bb",
+                    "315073",
+                    "00000000000000000000000000000000000000cc",
+                    "3b505f5f5f5f5f60045af15000",
+                ),
+                "storage": { "0x01": "0x2a" },
+            },
+        }))
+        .unwrap();
+        engine.load_alloc(alloc);
+
+        let tx = TxEnv {
+            caller,
+            kind: TxKind::Call(contract),
+            gas_limit: 100_000,
+            ..Default::default()
+        };
+        let created = engine.create_access_list(tx.clone()).unwrap();
+        assert!(created.result.result.is_success());
+
+        // NOTE: the recipient is included for its slots (i.e. as per geth), whereas the sender and
+        // precompiles are not
+        assert_eq!(
+            created.access_list,
+            AccessList(vec![
+                AccessListItem {
+                    address: contract,
+                    storage_keys: vec![B256::with_last_byte(1)],
+                },
+                AccessListItem {
+                    address: address!("00000000000000000000000000000000000000bb"),
+                    storage_keys: vec![],
+                },
+                AccessListItem {
+                    address: address!("00000000000000000000000000000000000000cc"),
+                    storage_keys: vec![],
+                },
+            ])
+        );
+
+        // Each (cold) access saves 100 gas, less the 2400 of listing the (warm) recipient
+        assert_eq!(created.gas_saved, 3 * 100 - 2400);
+        let (res, _events) = engine
+            .execute(TxEnv {
+                tx_type: TransactionType::Eip2930 as u8,
+                access_list: created.access_list.clone(),
+                ..tx.clone()
+            })
+            .unwrap();
+        assert_eq!(created.gas_used, res.result.gas_used());
+        // Accesses are only tracked to create an access list
+        assert!(engine.evm.inspector().accesses.is_empty());
+        assert_eq!(
+            engine.execute(tx.clone()).unwrap().0.result.gas_used() as i64,
+            created.gas_used as i64 + created.gas_saved
+        );
+
+        // The access list is not committed
+        assert_eq!(engine.dump_alloc().get(&caller), None);
+
+        // Legacy transactions are upgraded to carry the list, even without a chain ID (i.e. prior
+        // to EIP-155)
+        assert_eq!(tx.tx_type, TransactionType::Legacy as u8);
+        let legacy = engine
+            .create_access_list(TxEnv {
+                chain_id: None,
+                ..tx.clone()
+            })
+            .unwrap();
+        assert_eq!(legacy.access_list, created.access_list);
+        assert_eq!(legacy.gas_used, created.gas_used);
+
+        // Access lists require Berlin
+        let mut engine = Engine::new().with_spec(SpecId::ISTANBUL);
+        assert!(matches!(
+            engine.create_access_list(tx),
+            Err(EVMError::Custom(err)) if err.contains("Berlin")
+        ));
+    }
+
+    #[test]
+    fn alloc() {
+        let mut engine = Engine::new();
+
+        // NOTE: balances may be decimal, and storage keys and values unpadded (as accepted by geth)
+        let alloc: Alloc = serde_json::from_value(serde_json::json!({
+            "0x00000000000000000000000000000000000000aa": {
+                // Increments (and returns) a counter at slot 0
+                "code": "0x5f546001018060005560005260205ff3",
+                "nonce": "0x1",
+                "storage": { "0x00": "0x29" },
+            },
+            "0x00000000000000000000000000000000000000cc": {
+                "balance": "1000000000000000000",
+            },
+            // Empty (but existing) account
+            "0x00000000000000000000000000000000000000ee": {},
+        }))
+        .unwrap();
+        engine.load_alloc(alloc.clone());
+
+        // Dumping the state as loaded is lossless (i.e. including empty accounts)
+        assert_eq!(engine.dump_alloc(), alloc);
+
+        let (res, _events) = engine
+            .execute_and_commit(TxEnv {
+                caller: address!("00000000000000000000000000000000000000cc"),
+                kind: TxKind::Call(address!("00000000000000000000000000000000000000aa")),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            U256::from_be_slice(res.result.output().unwrap()),
+            U256::from(0x2a)
+        );
+
+        // NOTE: the (empty) coinbase touched by the transaction is deleted (as per EIP-161)
+        assert_eq!(
+            serde_json::to_value(engine.dump_alloc()).unwrap(),
+            serde_json::json!({
+                "0x00000000000000000000000000000000000000aa": {
+                    "balance": "0x0",
+                    "nonce": "0x1",
+                    "code": "0x5f546001018060005560005260205ff3",
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000000":
+                            "0x000000000000000000000000000000000000000000000000000000000000002a",
+                    },
+                },
+                "0x00000000000000000000000000000000000000cc": {
+                    "balance": "0xde0b6b3a7640000",
+                    "nonce": "0x1",
+                },
+                "0x00000000000000000000000000000000000000ee": { "balance": "0x0" },
+            })
+        );
+    }
+
+    #[test]
+    fn db() {
+        let address = address!("00000000000000000000000000000000000000aa");
+
+        // Returns the value of slot 5
+        let bytecode = Bytecode::new_raw(Bytes::from([
+            opcode::PUSH1, // `key`
+            0x05,
+            opcode::SLOAD,
+            opcode::PUSH0, // `offset`
+            opcode::MSTORE,
+            opcode::PUSH1, // `size`
+            0x20,
+            opcode::PUSH0, // `offset`
+            opcode::RETURN,
+        ]));
+
+        /// A (read-only) backend with a single contract, whose storage slots hold their key + 1
+        struct Mock(Address, Bytecode);
+
+        impl DatabaseRef for Mock {
+            type Error = std::convert::Infallible;
+
+            fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+                Ok((address == self.0).then(|| AccountInfo::from_bytecode(self.1.clone())))
+            }
+
+            fn code_by_hash_ref(&self, _code_hash: B256) -> Result<Bytecode, Self::Error> {
+                Ok(Bytecode::default())
+            }
+
+            fn storage_ref(&self, _address: Address, index: U256) -> Result<U256, Self::Error> {
+                Ok(index + U256::from(1))
+            }
+
+            fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+                Ok(revm::primitives::keccak256(number.to_be_bytes()))
+            }
+        }
+
+        let mut engine = Engine::new()
+            .with_spec(SpecId::CANCUN)
+            .with_db(Mock(address, bytecode.clone()));
+        assert_eq!(engine.spec(), SpecId::CANCUN);
+
+        let tx = TxEnv {
+            kind: TxKind::Call(address),
+            ..Default::default()
+        };
+
+        let (res, _events) = engine.execute(tx.clone()).unwrap();
+        assert_eq!(
+            U256::from_be_slice(res.result.output().unwrap()),
+            U256::from(6)
+        );
+
+        // Created accounts take precedence over the backend
+        engine.create_account(
+            address,
+            revm::state::Account::from(AccountInfo::from_bytecode(bytecode))
+                .with_storage([(U256::from(5), EvmStorageSlot::new(U256::from(42)))].into_iter()),
+        );
+        assert!(engine.db().cache.accounts.contains_key(&address));
+
+        let (res, _events) = engine.execute(tx).unwrap();
+        assert_eq!(
+            U256::from_be_slice(res.result.output().unwrap()),
+            U256::from(42)
+        );
+    }
 }
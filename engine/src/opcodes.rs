@@ -0,0 +1,69 @@
+//! Annotations for the stack inputs of each operation - see <https://www.evm.codes>
+//!
+//! NOTE: stack manipulations (i.e. `DUP*`, `SWAP*`, `DUPN`, `SWAPN` and `EXCHANGE`) are not
+//! annotated, as they do not consume their inputs
+
+use revm::bytecode::opcode::*;
+
+/// Names of the stack inputs consumed by an operation, from the top of the stack downwards
+pub fn inputs(op: u8) -> &'static [&'static str] {
+    match op {
+        ADD | MUL | SUB | DIV | SDIV | MOD | SMOD => &["a", "b"],
+        ADDMOD | MULMOD => &["a", "b", "N"],
+        EXP => &["a", "exponent"],
+        SIGNEXTEND => &["b", "x"],
+        LT | GT | SLT | SGT | EQ | AND | OR | XOR => &["a", "b"],
+        ISZERO | NOT => &["a"],
+        BYTE => &["i", "x"],
+        SHL | SHR | SAR => &["shift", "value"],
+        KECCAK256 => &["offset", "size"],
+        BALANCE | EXTCODESIZE | EXTCODEHASH | SELFDESTRUCT => &["address"],
+        CALLDATALOAD => &["i"],
+        CALLDATACOPY | CODECOPY | RETURNDATACOPY | MCOPY => &["destOffset", "offset", "size"],
+        EXTCODECOPY => &["address", "destOffset", "offset", "size"],
+        BLOCKHASH => &["blockNumber"],
+        BLOBHASH => &["index"],
+        POP => &["y"],
+        MLOAD => &["offset"],
+        MSTORE | MSTORE8 => &["offset", "value"],
+        SLOAD | TLOAD => &["key"],
+        SSTORE | TSTORE => &["key", "value"],
+        JUMP => &["counter"],
+        JUMPI => &["counter", "b"],
+        LOG0 => &["offset", "size"],
+        LOG1 => &["offset", "size", "topic0"],
+        LOG2 => &["offset", "size", "topic0", "topic1"],
+        LOG3 => &["offset", "size", "topic0", "topic1", "topic2"],
+        LOG4 => &["offset", "size", "topic0", "topic1", "topic2", "topic3"],
+        DATALOAD => &["offset"],
+        DATACOPY => &["memOffset", "offset", "size"],
+        RJUMPI => &["condition"],
+        RJUMPV => &["case"],
+        EOFCREATE => &["value", "salt", "inputOffset", "inputSize"],
+        RETURNCONTRACT => &["auxDataOffset", "auxDataSize"],
+        CREATE => &["value", "offset", "size"],
+        CREATE2 => &["value", "offset", "size", "salt"],
+        CALL | CALLCODE => &[
+            "gas",
+            "address",
+            "value",
+            "argsOffset",
+            "argsSize",
+            "retOffset",
+            "retSize",
+        ],
+        DELEGATECALL | STATICCALL => &[
+            "gas",
+            "address",
+            "argsOffset",
+            "argsSize",
+            "retOffset",
+            "retSize",
+        ],
+        RETURN | REVERT => &["offset", "size"],
+        RETURNDATALOAD => &["offset"],
+        EXTCALL => &["address", "argsOffset", "argsSize", "value"],
+        EXTDELEGATECALL | EXTSTATICCALL => &["address", "argsOffset", "argsSize"],
+        _ => &[],
+    }
+}
@@ -0,0 +1,346 @@
+//! Decoding of signed raw transactions - i.e. as broadcast by wallets (`eth_sendRawTransaction`)
+//!
+//! A raw transaction is either a legacy (RLP encoded) transaction, or an EIP-2718 typed envelope
+//! (i.e. the type byte followed by the RLP encoded fields) of an EIP-2930, EIP-1559, EIP-4844 or
+//! EIP-7702 transaction. Its sender is recovered from the signature - see [`decode`].
+//!
+//! # Example
+//!
+//! ```
+//! # use engine::raw;
+//! # use revm::primitives::{address, hex};
+//! // EIP-155 example transaction (i.e. signed by the key 0x4646...46)
+//! let tx = raw::decode(&hex!("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"))?;
+//! assert_eq!(tx.caller, address!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"));
+//! assert_eq!(tx.chain_id, Some(1));
+//! # Ok::<(), raw::DecodeError>(())
+//! ```
+
+use alloy_rlp::{Decodable, Header};
+use revm::{
+    context::{TxEnv, result::EVMError},
+    context_interface::transaction::TransactionType,
+    primitives::{Address, TxKind, alloy_primitives::Signature, keccak256},
+};
+use std::fmt;
+
+/// Error decoding a signed raw transaction
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeError {
+    /// The transaction is not validly encoded (e.g. truncated, or missing fields)
+    Rlp(alloy_rlp::Error),
+    /// The type of the (EIP-2718 typed) transaction is not supported
+    UnsupportedType(u8),
+    /// The signature is invalid - i.e. the sender cannot be recovered
+    Signature(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rlp(err) => write!(f, "rlp error: {err}"),
+            Self::UnsupportedType(tx_type) => {
+                write!(f, "unsupported transaction type: {tx_type:#x}")
+            }
+            Self::Signature(message) => write!(f, "invalid signature: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<alloy_rlp::Error> for DecodeError {
+    fn from(err: alloy_rlp::Error) -> Self {
+        Self::Rlp(err)
+    }
+}
+
+/// Error executing a signed raw transaction - see [`Engine::execute_raw`](crate::Engine::execute_raw)
+#[derive(Debug)]
+pub enum ExecuteError<E> {
+    /// The transaction could not be decoded (or its sender recovered)
+    Decode(DecodeError),
+    /// The transaction could not be executed (e.g. invalid nonce, or insufficient balance)
+    Evm(EVMError<E>),
+}
+
+impl<E: fmt::Display> fmt::Display for ExecuteError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(err) => err.fmt(f),
+            Self::Evm(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for ExecuteError<E> {}
+
+impl<E> From<DecodeError> for ExecuteError<E> {
+    fn from(err: DecodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl<E> From<EVMError<E>> for ExecuteError<E> {
+    fn from(err: EVMError<E>) -> Self {
+        Self::Evm(err)
+    }
+}
+
+/// Cursor over the (RLP encoded) fields of a transaction
+struct Fields<'a> {
+    payload: &'a [u8],
+    remaining: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(payload: &'a [u8]) -> Self {
+        Self {
+            payload,
+            remaining: payload,
+        }
+    }
+
+    fn next<T: Decodable>(&mut self) -> Result<T, DecodeError> {
+        Ok(T::decode(&mut self.remaining)?)
+    }
+
+    /// The encoded fields decoded so far
+    fn decoded(&self) -> &'a [u8] {
+        &self.payload[..self.payload.len() - self.remaining.len()]
+    }
+}
+
+/// Decodes the payload of an RLP list, advancing the buffer past it
+fn list<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], DecodeError> {
+    let header = Header::decode(buf)?;
+    if !header.list {
+        return Err(alloy_rlp::Error::UnexpectedString.into());
+    }
+    if buf.len() < header.payload_length {
+        return Err(alloy_rlp::Error::InputTooShort.into());
+    }
+    let (payload, rest) = buf.split_at(header.payload_length);
+    *buf = rest;
+    Ok(payload)
+}
+
+/// Encodes (encoded) fields as an RLP list, prefixed by the transaction type (if typed)
+fn encode_list(tx_type: Option<u8>, fields: &[&[u8]]) -> Vec<u8> {
+    let payload_length = fields.iter().map(|field| field.len()).sum();
+    let mut out = Vec::from_iter(tx_type);
+    Header {
+        list: true,
+        payload_length,
+    }
+    .encode(&mut out);
+    fields.iter().for_each(|field| out.extend_from_slice(field));
+    out
+}
+
+/// Decodes a signed raw transaction (i.e. legacy or EIP-2718 typed), recovering its sender (i.e.
+/// the `caller`) from the signature
+///
+/// NOTE: blob transactions may be in their network form (i.e. with the blobs, commitments and
+/// proofs), although the sidecar is not validated.
+pub fn decode(raw: &[u8]) -> Result<TxEnv, DecodeError> {
+    let (tx_type, mut buf) = match raw.first().copied() {
+        None => return Err(alloy_rlp::Error::InputTooShort.into()),
+        Some(0xc0..) => (TransactionType::Legacy, raw),
+        Some(1) => (TransactionType::Eip2930, &raw[1..]),
+        Some(2) => (TransactionType::Eip1559, &raw[1..]),
+        Some(3) => (TransactionType::Eip4844, &raw[1..]),
+        Some(4) => (TransactionType::Eip7702, &raw[1..]),
+        Some(tx_type @ ..=0x7f) => return Err(DecodeError::UnsupportedType(tx_type)),
+        Some(_) => return Err(alloy_rlp::Error::UnexpectedString.into()),
+    };
+
+    let mut payload = list(&mut buf)?;
+    if !buf.is_empty() {
+        return Err(alloy_rlp::Error::UnexpectedLength.into());
+    }
+    // NOTE: the network form of a blob transaction wraps its fields (i.e. in a list) alongside
+    // the sidecar
+    if tx_type == TransactionType::Eip4844 && payload.first().is_some_and(|&byte| byte >= 0xc0) {
+        payload = list(&mut payload)?;
+    }
+
+    let mut fields = Fields::new(payload);
+    let mut tx = TxEnv {
+        tx_type: tx_type as u8,
+        // NOTE: legacy transactions are not bound to a chain prior to EIP-155
+        chain_id: None,
+        ..Default::default()
+    };
+    if tx_type == TransactionType::Legacy {
+        tx.nonce = fields.next()?;
+        tx.gas_price = fields.next()?;
+        tx.gas_limit = fields.next()?;
+        tx.kind = fields.next()?;
+        tx.value = fields.next()?;
+        tx.data = fields.next()?;
+    } else {
+        tx.chain_id = Some(fields.next()?);
+        tx.nonce = fields.next()?;
+        if tx_type == TransactionType::Eip2930 {
+            tx.gas_price = fields.next()?;
+        } else {
+            tx.gas_priority_fee = Some(fields.next()?);
+            tx.gas_price = fields.next()?;
+        }
+        tx.gas_limit = fields.next()?;
+        tx.kind = match tx_type {
+            // NOTE: blob and set code transactions cannot create contracts
+            TransactionType::Eip4844 | TransactionType::Eip7702 => {
+                TxKind::Call(fields.next::<Address>()?)
+            }
+            _ => fields.next()?,
+        };
+        tx.value = fields.next()?;
+        tx.data = fields.next()?;
+        tx.access_list = fields.next()?;
+        if tx_type == TransactionType::Eip4844 {
+            tx.max_fee_per_blob_gas = fields.next()?;
+            tx.blob_hashes = fields.next()?;
+        }
+        if tx_type == TransactionType::Eip7702 {
+            tx.authorization_list = fields.next()?;
+        }
+    }
+
+    let unsigned = fields.decoded();
+    let v: u64 = fields.next()?;
+    let r = fields.next()?;
+    let s = fields.next()?;
+    if !fields.remaining.is_empty() {
+        return Err(alloy_rlp::Error::UnexpectedLength.into());
+    }
+
+    // The signed (i.e. hashed) preimage excludes the signature, although legacy transactions include
+    // the chain ID as of EIP-155 - i.e. `v = chain_id * 2 + 35 + y_parity`
+    let (y_parity, preimage) = match (tx_type, v) {
+        (TransactionType::Legacy, 27 | 28) => (v == 28, encode_list(None, &[unsigned])),
+        (TransactionType::Legacy, 35..) => {
+            let chain_id = (v - 35) / 2;
+            tx.chain_id = Some(chain_id);
+            (
+                (v - 35) % 2 == 1,
+                encode_list(
+                    None,
+                    &[unsigned, &alloy_rlp::encode(chain_id), &[0x80, 0x80]],
+                ),
+            )
+        }
+        (TransactionType::Legacy, _) => {
+            return Err(DecodeError::Signature(format!("invalid v: {v}")));
+        }
+        (_, 0 | 1) => (v == 1, encode_list(Some(tx_type as u8), &[unsigned])),
+        (_, _) => {
+            return Err(DecodeError::Signature(format!("invalid y parity: {v}")));
+        }
+    };
+
+    let signature = Signature::new(r, s, y_parity);
+    // NOTE: signatures with a high `s` value are malleable, so are invalid - see EIP-2
+    if signature.normalize_s().is_some() {
+        return Err(DecodeError::Signature("s value too high".into()));
+    }
+    tx.caller = signature
+        .recover_address_from_prehash(&keccak256(preimage))
+        .map_err(|err| DecodeError::Signature(err.to_string()))?;
+
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, Event};
+    use revm::{
+        context::result::InvalidTransaction,
+        primitives::{U256, address, hex},
+        state::AccountInfo,
+    };
+
+    #[test]
+    fn signed() {
+        // NOTE: signed by the key 0x45a9...f2d8 (i.e. the sender of the `ethereum/tests` fixtures)
+        let sender = address!("a94f5374fce5edbc8e2a8697c15331677e6ebf0b");
+        let dynamic_fee = hex!(
+            "02f89b0103020a8275309400000000000000000000000000000000000000aa0780f838f79400000000000000000000000000000000000000aae1a0000000000000000000000000000000000000000000000000000000000000000101a0f973a0b87062c389d125d8199e803b832b6ac6bf7867a4f6cd87506060fc4c58a03f606bcf6ee951994985e593355d063f04866b454c7e245f9c71184bba904fd5"
+        );
+
+        let mut engine = Engine::new();
+        engine.create_account(
+            sender,
+            AccountInfo::from_balance(U256::from(1_000_000)).with_nonce(3),
+        );
+        let (res, events) = engine.execute_raw(&dynamic_fee).unwrap();
+        assert!(res.result.is_success());
+        assert!(matches!(
+            &events[0],
+            Event::CallEnter(enter) if enter.from == sender && enter.value == U256::from(7)
+        ));
+
+        let tx = decode(&dynamic_fee).unwrap();
+        assert_eq!(tx.tx_type, 2);
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.gas_priority_fee, Some(2));
+        assert_eq!(tx.access_list.0.len(), 1);
+
+        // Legacy contract creation, i.e. prior to EIP-155 (and so without a chain ID)
+        let tx = decode(&hex!(
+            "f852800a830186a08080866001600055001ca0f973a0b87062c389d125d8199e803b832b6ac6bf7867a4f6cd87506060fc4c58a0594318e039b514615a2899f1c4103a5439823081cf6dc8f1cf929a0ec41a8a06"
+        ))
+        .unwrap();
+        assert_eq!(
+            (tx.caller, tx.kind, tx.chain_id),
+            (sender, TxKind::Create, None)
+        );
+
+        // Blob transaction in its network form (i.e. with the sidecar)
+        let tx = decode(&hex!(
+            "03f9012ff8850104020a8275309400000000000000000000000000000000000000aa8080c001e1a0010000000000000000000000000000000000000000000000000000000000000080a0f973a0b87062c389d125d8199e803b832b6ac6bf7867a4f6cd87506060fc4c58a0500123bb0760c6ccaafd1de0f8f1561a69e3088c28f95d5968eda0946b5bf4e4f842b84000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000f1b0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000f1b0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"
+        ))
+        .unwrap();
+        assert_eq!(
+            (tx.caller, tx.tx_type, tx.blob_hashes.len()),
+            (sender, 3, 1)
+        );
+
+        // Set code transaction, whose authorization is signed by the sender
+        let tx = decode(&hex!(
+            "04f8c00105020a82ea609400000000000000000000000000000000000000aa8080c0f85cf85a019400000000000000000000000000000000000000aa8001a0079264c4b4bfcd7fe3a7b7b92b6c439f3a5b3abcd29189bf7b54d781ff03d722a00d4ebee310371374b18041062ed163ba4ad106874a3f5dfae273d08c9a942bf480a0f973a0b87062c389d125d8199e803b832b6ac6bf7867a4f6cd87506060fc4c58a0734bd8bbdd9b8cad72e9168e7e0e7cfa5a2e1f2263c87b9ddc8e137a7786b530"
+        ))
+        .unwrap();
+        assert_eq!(tx.caller, sender);
+        assert_eq!(
+            tx.authorization_list[0].recover_authority().unwrap(),
+            sender
+        );
+
+        // NOTE: the chain ID (i.e. 5, as per EIP-155) must match that of the engine
+        let eip155 = hex!(
+            "f85f010a8252089400000000000000000000000000000000000000aa05802da0f973a0b87062c389d125d8199e803b832b6ac6bf7867a4f6cd87506060fc4c58a040a71901ff964d7d5d6892055d4a9a71e711271cefccadc98006ead0529f4aba"
+        );
+        assert!(matches!(
+            engine.execute_raw(&eip155),
+            Err(ExecuteError::Evm(EVMError::Transaction(
+                InvalidTransaction::InvalidChainId
+            )))
+        ));
+
+        assert_eq!(decode(&hex!("05c0")), Err(DecodeError::UnsupportedType(5)));
+        assert_eq!(
+            decode(&dynamic_fee[..dynamic_fee.len() - 1]),
+            Err(DecodeError::Rlp(alloy_rlp::Error::InputTooShort))
+        );
+        // A malleable (i.e. high `s`) signature is rejected, as per EIP-2
+        assert!(matches!(
+            decode(&hex!(
+                "02f89b0103020a8275309400000000000000000000000000000000000000aa0780f838f79400000000000000000000000000000000000000aae1a0000000000000000000000000000000000000000000000000000000000000000180a0f973a0b87062c389d125d8199e803b832b6ac6bf7867a4f6cd87506060fc4c58a0c09f94309116ae66b67a1a6ccaa2f9bfb62871a162ca7bdc2361464115a5f16c"
+            )),
+            Err(DecodeError::Signature(_))
+        ));
+    }
+}
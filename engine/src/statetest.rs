@@ -0,0 +1,687 @@
+//! Runner for the Ethereum `GeneralStateTests` - see <https://github.com/ethereum/tests>
+//!
+//! Each test of a fixture (i.e. JSON file) is executed through the [`Engine`] (with tracing
+//! enabled) for every fork it targets, and the resulting post-state root and logs hash are checked
+//! against those expected - i.e. the tracer does not perturb execution.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use engine::statetest;
+//! for path in statetest::find("GeneralStateTests/stExample".as_ref())? {
+//!     for outcome in statetest::run(&path)? {
+//!         println!("{outcome}");
+//!     }
+//! }
+//! # Ok::<(), statetest::Error>(())
+//! ```
+
+use crate::{Engine, TraceConfig, alloc::Alloc};
+use k256::ecdsa::SigningKey;
+use revm::{
+    context::{BlockEnv, TxEnv, result::EVMError},
+    context_interface::transaction::{
+        AccessList, Authorization as AuthorizationInner, SignedAuthorization, TransactionType,
+    },
+    primitives::{
+        Address, B256, Bytes, Log, TxKind, U256,
+        alloy_primitives::{U8, U64},
+        hardfork::SpecId,
+        keccak256,
+    },
+};
+use serde::{Deserialize, Deserializer};
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Error reading (or parsing) a fixture
+#[derive(Debug)]
+pub enum Error {
+    /// The fixture could not be read
+    Io(io::Error),
+    /// The fixture is not a valid `GeneralStateTests` fixture
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {err}"),
+            Self::Parse(err) => write!(f, "parse error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+/// The block environment of a test
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Env {
+    current_coinbase: Address,
+    #[serde(default)]
+    current_difficulty: U256,
+    current_gas_limit: U256,
+    current_number: U256,
+    current_timestamp: U256,
+    #[serde(default)]
+    current_base_fee: Option<U256>,
+    #[serde(default)]
+    current_random: Option<B256>,
+    #[serde(default)]
+    current_excess_blob_gas: Option<U256>,
+}
+
+impl Env {
+    fn block_env(&self, spec: SpecId) -> BlockEnv {
+        let mut block = BlockEnv {
+            number: self.current_number.saturating_to(),
+            beneficiary: self.current_coinbase,
+            timestamp: self.current_timestamp.saturating_to(),
+            gas_limit: self.current_gas_limit.saturating_to(),
+            basefee: self.current_base_fee.unwrap_or_default().saturating_to(),
+            difficulty: self.current_difficulty,
+            // NOTE: the randomness replaces the difficulty as of the merge - see EIP-4399
+            prevrandao: Some(self.current_random.unwrap_or_default()),
+            ..Default::default()
+        };
+        if let Some(excess_blob_gas) = self.current_excess_blob_gas {
+            block.set_blob_excess_gas_and_price(
+                excess_blob_gas.saturating_to(),
+                spec.is_enabled_in(SpecId::PRAGUE),
+            );
+        }
+        block
+    }
+}
+
+/// A signed authorization (of a set code transaction) - see EIP-7702
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Authorization {
+    chain_id: U256,
+    address: Address,
+    nonce: U64,
+    // NOTE: older fixtures provide the signature's `v` (rather than `yParity`)
+    #[serde(default)]
+    y_parity: Option<U8>,
+    #[serde(default)]
+    v: Option<U8>,
+    r: U256,
+    s: U256,
+}
+
+impl From<&Authorization> for SignedAuthorization {
+    fn from(authorization: &Authorization) -> Self {
+        SignedAuthorization::new_unchecked(
+            AuthorizationInner {
+                chain_id: authorization.chain_id,
+                address: authorization.address,
+                nonce: authorization.nonce.to(),
+            },
+            authorization
+                .y_parity
+                .or(authorization.v)
+                .unwrap_or_default()
+                .to(),
+            authorization.r,
+            authorization.s,
+        )
+    }
+}
+
+/// The transaction of a test, whose data, gas limit and value are indexed by each expectation
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Transaction {
+    data: Vec<Bytes>,
+    gas_limit: Vec<U256>,
+    value: Vec<U256>,
+    nonce: U256,
+    #[serde(default)]
+    gas_price: Option<U256>,
+    #[serde(default)]
+    max_fee_per_gas: Option<U256>,
+    #[serde(default)]
+    max_priority_fee_per_gas: Option<U256>,
+    #[serde(default)]
+    sender: Option<Address>,
+    #[serde(default)]
+    secret_key: Option<B256>,
+    #[serde(default, deserialize_with = "to")]
+    to: Option<Address>,
+    #[serde(default)]
+    access_lists: Vec<Option<AccessList>>,
+    #[serde(default)]
+    blob_versioned_hashes: Vec<B256>,
+    #[serde(default)]
+    max_fee_per_blob_gas: Option<U256>,
+    #[serde(default)]
+    authorization_list: Option<Vec<Authorization>>,
+}
+
+/// Deserializes the recipient of a transaction - i.e. empty for a contract creation
+fn to<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Address>, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "" => Ok(None),
+        to => to.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+impl Transaction {
+    /// The transaction for the indexes (or `None` if invalid, e.g. a blob contract creation)
+    fn tx_env(&self, indexes: Indexes) -> Option<TxEnv> {
+        let access_list = self.access_lists.get(indexes.data).cloned().flatten();
+
+        // NOTE: the type is determined by the fields present (rather than their values),
+        // e.g. an empty authorization list is an (invalid) set code transaction
+        let tx_type = match self {
+            Self {
+                authorization_list: Some(_),
+                to,
+                ..
+            } => to.and(Some(TransactionType::Eip7702))?,
+            Self {
+                max_fee_per_blob_gas: Some(_),
+                to,
+                ..
+            } => to.and(Some(TransactionType::Eip4844))?,
+            Self {
+                max_fee_per_gas: Some(_),
+                ..
+            } => TransactionType::Eip1559,
+            _ if access_list.is_some() => TransactionType::Eip2930,
+            _ => TransactionType::Legacy,
+        };
+
+        Some(TxEnv {
+            tx_type: tx_type as u8,
+            caller: self.sender.or_else(|| self.secret_key.and_then(address))?,
+            gas_limit: self.gas_limit.get(indexes.gas)?.saturating_to(),
+            gas_price: self
+                .gas_price
+                .or(self.max_fee_per_gas)
+                .unwrap_or_default()
+                .saturating_to(),
+            kind: self.to.map_or(TxKind::Create, TxKind::Call),
+            value: *self.value.get(indexes.value)?,
+            data: self.data.get(indexes.data)?.clone(),
+            nonce: self.nonce.saturating_to(),
+            chain_id: Some(1),
+            access_list: access_list.unwrap_or_default(),
+            gas_priority_fee: self.max_priority_fee_per_gas.map(|fee| fee.saturating_to()),
+            blob_hashes: self.blob_versioned_hashes.clone(),
+            max_fee_per_blob_gas: self
+                .max_fee_per_blob_gas
+                .unwrap_or_default()
+                .saturating_to(),
+            authorization_list: self
+                .authorization_list
+                .iter()
+                .flatten()
+                .map(SignedAuthorization::from)
+                .collect(),
+        })
+    }
+}
+
+/// The address of a secret (i.e. private) key
+fn address(secret_key: B256) -> Option<Address> {
+    let key = SigningKey::from_slice(secret_key.as_slice()).ok()?;
+    let public_key = key.verifying_key().to_encoded_point(false);
+    Some(Address::from_raw_public_key(&public_key.as_bytes()[1..]))
+}
+
+/// Indexes of the data, gas limit and value of the transaction of a test
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct Indexes {
+    /// Index of the data (and access list)
+    pub data: usize,
+    /// Index of the gas limit
+    pub gas: usize,
+    /// Index of the value
+    pub value: usize,
+}
+
+/// The expected outcome of a test (for a fork)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Expectation {
+    indexes: Indexes,
+    hash: B256,
+    logs: B256,
+    #[serde(default)]
+    expect_exception: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Test {
+    env: Env,
+    pre: Alloc,
+    transaction: Transaction,
+    post: BTreeMap<String, Vec<Expectation>>,
+}
+
+/// Reason a test failed
+#[derive(Clone, Debug, PartialEq)]
+pub enum Failure {
+    /// The transaction failed (or succeeded) other than expected
+    UnexpectedException {
+        /// The expected exception (if any)
+        expected: Option<String>,
+        /// The exception encountered (if any)
+        actual: Option<String>,
+    },
+    /// The hash of the logs differs from that expected
+    LogsHash {
+        /// The expected hash
+        expected: B256,
+        /// The actual hash
+        actual: B256,
+    },
+    /// The post-state root differs from that expected
+    StateRoot {
+        /// The expected root
+        expected: B256,
+        /// The actual root
+        actual: B256,
+    },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedException { expected, actual } => write!(
+                f,
+                "expected exception {expected:?}, got {actual:?}",
+                expected = expected.as_deref().unwrap_or("none"),
+                actual = actual.as_deref().unwrap_or("none"),
+            ),
+            Self::LogsHash { expected, actual } => {
+                write!(f, "logs hash mismatch: expected {expected}, got {actual}")
+            }
+            Self::StateRoot { expected, actual } => {
+                write!(f, "state root mismatch: expected {expected}, got {actual}")
+            }
+        }
+    }
+}
+
+/// Outcome of a test, for a fork and the indexes of its transaction
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+    /// Name of the test
+    pub name: String,
+    /// Name of the fork (as per the fixture, e.g. `Cancun`)
+    pub fork: String,
+    /// Indexes of the transaction's data, gas limit and value
+    pub indexes: Indexes,
+    /// Whether the test passed (or why it failed) - `None` if skipped, i.e. the fork is not
+    /// supported by the engine
+    pub result: Option<Result<(), Failure>>,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Indexes { data, gas, value } = self.indexes;
+        let status = match self.result {
+            None => "SKIP",
+            Some(Ok(())) => "PASS",
+            Some(Err(_)) => "FAIL",
+        };
+        write!(
+            f,
+            "{status} {} {} d={data} g={gas} v={value}",
+            self.name, self.fork
+        )?;
+        match &self.result {
+            None => write!(f, ": unsupported fork"),
+            Some(Ok(())) => Ok(()),
+            Some(Err(failure)) => write!(f, ": {failure}"),
+        }
+    }
+}
+
+/// The hardfork of a fork (as named by the fixtures)
+///
+/// NOTE: `Constantinople` (i.e. with EIP-1283, as reverted by `ConstantinopleFix`) is not
+/// supported by the engine, nor is `Osaka` (i.e. revm's `OSAKA` is the EOF fork), so their tests
+/// are skipped
+fn spec(fork: &str) -> Option<SpecId> {
+    Some(match fork {
+        "Frontier" => SpecId::FRONTIER,
+        "Homestead" => SpecId::HOMESTEAD,
+        "EIP150" => SpecId::TANGERINE,
+        "EIP158" => SpecId::SPURIOUS_DRAGON,
+        "Byzantium" => SpecId::BYZANTIUM,
+        "ConstantinopleFix" => SpecId::PETERSBURG,
+        "Istanbul" => SpecId::ISTANBUL,
+        "Berlin" => SpecId::BERLIN,
+        "London" => SpecId::LONDON,
+        "Paris" | "Merge" => SpecId::MERGE,
+        "Shanghai" => SpecId::SHANGHAI,
+        "Cancun" => SpecId::CANCUN,
+        "Prague" => SpecId::PRAGUE,
+        _ => return None,
+    })
+}
+
+/// Finds the fixtures (i.e. JSON files) at a path - i.e. the file, or those within the directory
+/// (recursively), in order
+pub fn find(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            paths.extend(find(&path)?);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Runs each test of a fixture (for every fork), returning their outcomes
+pub fn run(path: &Path) -> Result<Vec<Outcome>, Error> {
+    let tests: BTreeMap<String, Test> = serde_json::from_slice(&fs::read(path)?)?;
+
+    let mut outcomes = Vec::new();
+    for (name, test) in &tests {
+        for (fork, expectations) in &test.post {
+            for expectation in expectations {
+                outcomes.push(Outcome {
+                    name: name.clone(),
+                    fork: fork.clone(),
+                    indexes: expectation.indexes,
+                    result: spec(fork).map(|spec| execute(test, spec, expectation)),
+                });
+            }
+        }
+    }
+    Ok(outcomes)
+}
+
+fn execute(test: &Test, spec: SpecId, expectation: &Expectation) -> Result<(), Failure> {
+    let mut engine = Engine::new()
+        .with_trace_config(TraceConfig {
+            enable_return_data: true,
+            enable_storage: true,
+            enable_stack_inputs: true,
+            // NOTE: the steps (i.e. stack and memory) of long-running tests are not captured,
+            // although they are still traced
+            limit: Some(0x10000),
+            ..Default::default()
+        })
+        .with_spec(spec)
+        .with_block(test.env.block_env(spec));
+    engine.load_alloc(test.pre.clone());
+
+    // NOTE: the error is paired with whether the transaction (or block) was rejected as invalid,
+    // i.e. rather than the engine failing to execute it
+    let result = match test.transaction.tx_env(expectation.indexes) {
+        Some(tx) => engine
+            .execute_and_commit(tx)
+            .map(|(res, _events)| res.result)
+            .map_err(|err| {
+                let rejected = matches!(err, EVMError::Transaction(_) | EVMError::Header(_));
+                (rejected, err.to_string())
+            }),
+        None => Err((true, "invalid transaction".to_string())),
+    };
+
+    match (&expectation.expect_exception, &result) {
+        (None, Ok(_)) => {}
+        // NOTE: a rejected transaction has no effect, so the (unchanged) state is checked as well
+        (Some(_), Err((true, _))) => {}
+        (expected, result) => {
+            return Err(Failure::UnexpectedException {
+                expected: expected.clone(),
+                actual: result.as_ref().err().map(|(_, err)| err.clone()),
+            });
+        }
+    }
+
+    let logs = logs_hash(result.as_ref().map_or(&[], |result| result.logs()));
+    if logs != expectation.logs {
+        return Err(Failure::LogsHash {
+            expected: expectation.logs,
+            actual: logs,
+        });
+    }
+
+    let root = engine.state_root();
+    if root != expectation.hash {
+        return Err(Failure::StateRoot {
+            expected: expectation.hash,
+            actual: root,
+        });
+    }
+
+    Ok(())
+}
+
+/// Hash of the (RLP encoded) logs of a transaction
+fn logs_hash(logs: &[Log]) -> B256 {
+    let mut out = Vec::new();
+    alloy_rlp::encode_list(logs, &mut out);
+    keccak256(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trie;
+    use revm::primitives::{LogData, address};
+
+    #[test]
+    fn fixture() {
+        // NOTE: the expected roots (and logs hashes) are not those of the engine, but of the
+        // post-states (and logs) derived by hand below - i.e. independently of its execution
+        let sender = "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+        let coinbase = "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba";
+        // Stores 0x2a at slot 0, and logs (with topic 0xaa) no data
+        let code = "0x602a5f5560aa5f5fa100";
+        let pre = serde_json::json!({
+            sender: {
+                "balance": "0x0de0b6b3a7640000",
+                "code": "0x",
+                "nonce": "0x00",
+                "storage": {},
+            },
+            "0x0000000000000000000000000000000000001000": {
+                "balance": "0x00",
+                "code": code,
+                "nonce": "0x01",
+                "storage": {},
+            },
+        });
+        let root = |alloc: serde_json::Value| {
+            trie::state_root(&serde_json::from_value::<Alloc>(alloc).unwrap()).to_string()
+        };
+        let empty_logs = logs_hash(&[]).to_string();
+
+        // The call costs 21000 (intrinsic) + 3 + 2 + 22100 (cold SSTORE of a zero slot) + 3 + 2 +
+        // 2 + 750 (LOG1) = 43862 gas, at 10 wei - i.e. none of which is paid to the coinbase, as
+        // the gas price is the base fee
+        let log = root(serde_json::json!({
+            sender: { "balance": "999999999999561380", "nonce": "0x1" },
+            "0x0000000000000000000000000000000000001000": {
+                "code": code,
+                "nonce": "0x1",
+                "storage": { "0x00": "0x2a" },
+            },
+        }));
+        let log_logs = logs_hash(&[Log {
+            address: address!("0000000000000000000000000000000000001000"),
+            data: LogData::new_unchecked(vec![B256::with_last_byte(0xaa)], Bytes::new()),
+        }])
+        .to_string();
+        // The transfer costs 21000 gas, at 10 wei - i.e. all of which is paid to the coinbase prior
+        // to London (see EIP-1559), and the (empty) recipient exists only prior to EIP-158 (i.e.
+        // EIP-161)
+        let transfer = |empty: bool| {
+            let mut alloc = serde_json::json!({
+                sender: { "balance": "999999999999790000", "nonce": "0x1" },
+                "0x0000000000000000000000000000000000001000": { "code": code, "nonce": "0x1" },
+                coinbase: { "balance": "210000" },
+            });
+            if empty {
+                alloc["0x0000000000000000000000000000000000003000"] = serde_json::json!({});
+            }
+            root(alloc)
+        };
+        // A rejected transaction leaves the pre-state unchanged
+        let unchanged = root(pre.clone());
+
+        let env = serde_json::json!({
+            "currentCoinbase": coinbase,
+            "currentDifficulty": "0x020000",
+            "currentGasLimit": "0x05f5e100",
+            "currentNumber": "0x01",
+            "currentTimestamp": "0x03e8",
+            "currentBaseFee": "0x0a",
+        });
+        let transaction = |to: &str, nonce: &str| {
+            serde_json::json!({
+                "data": ["0x"],
+                "gasLimit": ["0x0186a0"],
+                "gasPrice": "0x0a",
+                "nonce": nonce,
+                "secretKey": "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+                "to": to,
+                "value": ["0x00"],
+            })
+        };
+        let expect = |hash: &str, logs: &str| {
+            serde_json::json!([{
+                "indexes": { "data": 0, "gas": 0, "value": 0 },
+                "hash": hash,
+                "logs": logs,
+            }])
+        };
+        let rejected = |hash: &str| {
+            let mut expected = expect(hash, &empty_logs);
+            expected[0]["expectException"] = "TR_NonceTooHigh".into();
+            expected
+        };
+
+        let fixture = serde_json::json!({
+            "log": {
+                "env": env,
+                "pre": pre,
+                "transaction": transaction("0x0000000000000000000000000000000000001000", "0x00"),
+                "post": {
+                    "Cancun": expect(&log, &log_logs),
+                    "Constantinople": expect(&log, &log_logs),
+                    "Osaka": expect(&log, &log_logs),
+                },
+            },
+            "touchEmpty": {
+                "env": env,
+                "pre": pre,
+                "transaction": transaction("0x0000000000000000000000000000000000003000", "0x00"),
+                "post": {
+                    "EIP150": expect(&transfer(true), &empty_logs),
+                    "EIP158": expect(&transfer(false), &empty_logs),
+                    // NOTE: deliberately wrong (i.e. as if the empty account were not deleted)
+                    "Byzantium": expect(&transfer(true), &empty_logs),
+                },
+            },
+            "nonceTooHigh": {
+                "env": env,
+                "pre": pre,
+                "transaction": transaction("0x0000000000000000000000000000000000001000", "0x01"),
+                "post": {
+                    "Cancun": rejected(&unchanged),
+                    // NOTE: deliberately wrong (i.e. the state is checked despite the rejection)
+                    "Shanghai": rejected(&trie::EMPTY_ROOT_HASH.to_string()),
+                },
+            },
+        });
+
+        let path = std::env::temp_dir().join(format!("engine-statetest-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(path.join("stExample")).unwrap();
+        std::fs::write(path.join("stExample/example.json"), fixture.to_string()).unwrap();
+        std::fs::write(path.join("README.md"), "").unwrap();
+
+        let paths = find(&path).unwrap();
+        assert_eq!(paths, [path.join("stExample/example.json")]);
+
+        let outcomes = run(&paths[0]).unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+
+        let results: Vec<_> = outcomes
+            .iter()
+            .map(|outcome| {
+                assert_eq!(
+                    outcome.indexes,
+                    Indexes {
+                        data: 0,
+                        gas: 0,
+                        value: 0
+                    }
+                );
+                (
+                    outcome.name.as_str(),
+                    outcome.fork.as_str(),
+                    outcome.result.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("log", "Cancun", Some(Ok(()))),
+                ("log", "Constantinople", None),
+                ("log", "Osaka", None),
+                ("nonceTooHigh", "Cancun", Some(Ok(()))),
+                (
+                    "nonceTooHigh",
+                    "Shanghai",
+                    Some(Err(Failure::StateRoot {
+                        expected: trie::EMPTY_ROOT_HASH,
+                        actual: unchanged.parse().unwrap(),
+                    }))
+                ),
+                (
+                    "touchEmpty",
+                    "Byzantium",
+                    Some(Err(Failure::StateRoot {
+                        expected: transfer(true).parse().unwrap(),
+                        actual: transfer(false).parse().unwrap(),
+                    }))
+                ),
+                ("touchEmpty", "EIP150", Some(Ok(()))),
+                ("touchEmpty", "EIP158", Some(Ok(()))),
+            ]
+        );
+        assert_eq!(
+            outcomes[1].to_string(),
+            "SKIP log Constantinople d=0 g=0 v=0: unsupported fork"
+        );
+    }
+}
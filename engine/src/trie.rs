@@ -0,0 +1,396 @@
+//! Commitments (i.e. Merkle Patricia trie roots) of the EVM state - i.e. the state root, committing
+//! to each account (by the keccak hash of its address), and the storage root of each account,
+//! committing to its storage (by the keccak hash of each slot)
+//!
+//! As the roots are those of the execution layer, they can be compared with those of other clients
+//! (e.g. the `stateRoot` of a block header, or the storage hash of `eth_getProof`). Proofs of
+//! accounts and their storage (see [`AccountProof`]) are verified against a state root by
+//! [`verify`].
+//!
+//! # Example
+//!
+//! ```
+//! # use engine::{alloc::{Alloc, GenesisAccount}, trie};
+//! # use revm::primitives::{U256, address, b256};
+//! let account = GenesisAccount {
+//!     storage: [(U256::from(1), U256::from(0x2a))].into(),
+//!     ..Default::default()
+//! };
+//! assert_eq!(
+//!     trie::storage_root(&account.storage),
+//!     b256!("fcbdb9e7191a6bc6efbe2e1903a50bd3c79312366db1e46acf7e94788c2b4c3e")
+//! );
+//!
+//! // NOTE: the state root of an empty state is that of an empty trie
+//! assert_eq!(trie::state_root(&Alloc::new()), trie::EMPTY_ROOT_HASH);
+//! ```
+
+use crate::alloc::{self, Alloc, GenesisAccount};
+use alloy_trie::{HashBuilder, Nibbles, proof::ProofRetainer};
+use revm::primitives::{Address, B256, Bytes, KECCAK_EMPTY, U256, keccak256};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+pub use alloy_trie::{EMPTY_ROOT_HASH, TrieAccount, proof::ProofVerificationError};
+
+/// Leaves of a trie - i.e. the (RLP encoded) values by their (hashed) keys
+type Leaves = BTreeMap<B256, Vec<u8>>;
+
+fn storage_leaves<'a>(storage: impl IntoIterator<Item = (&'a U256, &'a U256)>) -> Leaves {
+    storage
+        .into_iter()
+        .filter(|(_, value)| !value.is_zero())
+        .map(|(slot, value)| (keccak256(B256::from(*slot)), alloy_rlp::encode(value)))
+        .collect()
+}
+
+fn state_leaves(alloc: &Alloc) -> Leaves {
+    alloc
+        .iter()
+        .map(|(address, genesis)| (keccak256(address), alloy_rlp::encode(account(genesis))))
+        .collect()
+}
+
+/// Computes the root of a trie, and the proof of each of the (hashed) keys - i.e. the nodes on the
+/// path from the root to the key (or where the path diverges from it, if absent)
+fn build(leaves: &Leaves, keys: &[B256]) -> (B256, Vec<Vec<Bytes>>) {
+    let targets = keys.iter().map(Nibbles::unpack).collect();
+    let mut builder = HashBuilder::default().with_proof_retainer(ProofRetainer::new(targets));
+    for (key, value) in leaves {
+        builder.add_leaf(Nibbles::unpack(key), value);
+    }
+    let root = builder.root();
+
+    // NOTE: as per geth, the proofs of an empty trie are empty (i.e. rather than the empty
+    // root node)
+    let nodes = builder.take_proof_nodes();
+    let proofs = keys
+        .iter()
+        .map(|key| match root {
+            EMPTY_ROOT_HASH => Vec::new(),
+            _ => nodes
+                .matching_nodes_sorted(&Nibbles::unpack(key))
+                .into_iter()
+                .map(|(_, node)| node)
+                .collect(),
+        })
+        .collect();
+    (root, proofs)
+}
+
+/// Root of the storage trie of an account (zero-valued slots are omitted, as if cleared)
+pub fn storage_root<'a>(storage: impl IntoIterator<Item = (&'a U256, &'a U256)>) -> B256 {
+    build(&storage_leaves(storage), &[]).0
+}
+
+/// An account as committed to by the state trie - i.e. its nonce, balance, storage root and code
+/// hash
+pub fn account(account: &GenesisAccount) -> TrieAccount {
+    TrieAccount {
+        nonce: account.nonce,
+        balance: account.balance,
+        storage_root: storage_root(&account.storage),
+        code_hash: keccak256(&account.code),
+    }
+}
+
+/// Root of the state trie of a state dump (e.g. a genesis `alloc`)
+pub fn state_root(alloc: &Alloc) -> B256 {
+    build(&state_leaves(alloc), &[]).0
+}
+
+/// Proof of an account and slots of its storage, against a state root - i.e. as returned by
+/// `eth_getProof` (see EIP-1186)
+///
+/// NOTE: an account absent from the state is proven as such (i.e. with the fields of an empty
+/// account), as are zero-valued slots.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProof {
+    /// Address of the account
+    pub address: Address,
+    /// Nodes of the state trie on the path to the account (from the root)
+    pub account_proof: Vec<Bytes>,
+    /// Balance of the account (in wei)
+    pub balance: U256,
+    /// Hash of the code of the account
+    pub code_hash: B256,
+    /// Nonce of the account
+    #[serde(with = "alloc::quantity")]
+    pub nonce: u64,
+    /// Root of the storage trie of the account
+    pub storage_hash: B256,
+    /// Proofs of the slots of the storage (in the order requested)
+    pub storage_proof: Vec<StorageProof>,
+}
+
+/// Proof of a slot of an account's storage, against its storage root - see [`AccountProof`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageProof {
+    /// Slot of the storage
+    pub key: B256,
+    /// Value of the slot
+    pub value: U256,
+    /// Nodes of the storage trie on the path to the slot (from the root)
+    pub proof: Vec<Bytes>,
+}
+
+/// Proves an account of a state dump (e.g. a genesis `alloc`), and slots of its storage
+pub fn prove(alloc: &Alloc, address: Address, slots: &[U256]) -> AccountProof {
+    let empty = GenesisAccount::default();
+    let genesis = alloc.get(&address).unwrap_or(&empty);
+
+    let keys = slots
+        .iter()
+        .map(|slot| keccak256(B256::from(*slot)))
+        .collect::<Vec<_>>();
+    let (storage_hash, proofs) = build(&storage_leaves(&genesis.storage), &keys);
+    let (_, account_proof) = build(&state_leaves(alloc), &[keccak256(address)]);
+
+    AccountProof {
+        address,
+        account_proof: account_proof.into_iter().next().unwrap_or_default(),
+        balance: genesis.balance,
+        code_hash: keccak256(&genesis.code),
+        nonce: genesis.nonce,
+        storage_hash,
+        storage_proof: slots
+            .iter()
+            .zip(proofs)
+            .map(|(slot, proof)| StorageProof {
+                key: B256::from(*slot),
+                value: genesis.storage.get(slot).copied().unwrap_or_default(),
+                proof,
+            })
+            .collect(),
+    }
+}
+
+/// Error verifying an [`AccountProof`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProofError {
+    /// The account is not proven by the account proof (against the state root)
+    Account(ProofVerificationError),
+    /// A slot is not proven by its storage proof (against the storage hash)
+    Storage {
+        /// Slot of the storage
+        key: B256,
+        /// Reason the slot is not proven
+        error: ProofVerificationError,
+    },
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Account(err) => write!(f, "invalid account proof: {err}"),
+            Self::Storage { key, error } => write!(f, "invalid storage proof of {key}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+/// Verifies a proof of an account, and the slots of its storage, against a state root
+pub fn verify(state_root: B256, proof: &AccountProof) -> Result<(), ProofError> {
+    let account = TrieAccount {
+        nonce: proof.nonce,
+        balance: proof.balance,
+        storage_root: proof.storage_hash,
+        code_hash: proof.code_hash,
+    };
+    let key = Nibbles::unpack(keccak256(proof.address));
+    let verify = |value| {
+        alloy_trie::proof::verify_proof(state_root, key, value, &proof.account_proof)
+            .map_err(ProofError::Account)
+    };
+    // NOTE: an empty account is either absent from the state (as per `eth_getProof`), or exists
+    // (e.g. in an alloc, or prior to EIP-161), whichever the proof is of
+    let empty = account.nonce == 0
+        && account.balance.is_zero()
+        && account.storage_root == EMPTY_ROOT_HASH
+        && account.code_hash == KECCAK_EMPTY;
+    if !empty || verify(None).is_err() {
+        verify(Some(alloy_rlp::encode(account)))?;
+    }
+
+    for storage in &proof.storage_proof {
+        alloy_trie::proof::verify_proof(
+            proof.storage_hash,
+            Nibbles::unpack(keccak256(storage.key)),
+            (!storage.value.is_zero()).then(|| alloy_rlp::encode(storage.value)),
+            &storage.proof,
+        )
+        .map_err(|error| ProofError::Storage {
+            key: storage.key,
+            error,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use revm::{
+        context::TxEnv,
+        primitives::{TxKind, address, b256},
+    };
+
+    #[test]
+    fn roots() {
+        let mut engine = Engine::new();
+
+        // The state of an empty engine is that of an empty trie
+        assert_eq!(engine.state_root(), EMPTY_ROOT_HASH);
+
+        let contract = address!("00000000000000000000000000000000000000aa");
+        engine.create_account(
+            contract,
+            GenesisAccount {
+                balance: U256::from(1_000_000_000_000_000_000_u64),
+                nonce: 1,
+                code: Bytes::from_static(&[0x60, 0x01, 0x60, 0x00, 0x55, 0x00]),
+                // NOTE: zero-valued slots are omitted from the storage trie (i.e. as if cleared)
+                storage: [
+                    (U256::from(1), U256::from(0x2a)),
+                    (U256::from(2), U256::ZERO),
+                    (U256::from(0x100), U256::from(7)),
+                ]
+                .into(),
+            },
+        );
+        engine.create_account(
+            address!("00000000000000000000000000000000000000bb"),
+            GenesisAccount {
+                balance: U256::from(5),
+                ..Default::default()
+            },
+        );
+        engine.create_account(
+            address!("00000000000000000000000000000000000000cc"),
+            GenesisAccount {
+                nonce: 3,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            engine.storage_root(contract),
+            Some(b256!(
+                "8a6cb9cbb2cf0101d37c495a8efb89656e34646feee4bbe275efbfd660b1a1d9"
+            ))
+        );
+        assert_eq!(
+            engine.storage_root(address!("00000000000000000000000000000000000000bb")),
+            Some(EMPTY_ROOT_HASH)
+        );
+        assert_eq!(
+            engine.storage_root(address!("00000000000000000000000000000000000000dd")),
+            None
+        );
+        assert_eq!(
+            engine.state_root(),
+            b256!("aebd1cde7eaf2ee931dfa1f4571d04a1b8e597dfd1f908baca26428cdc063394")
+        );
+        assert_eq!(engine.state_root(), state_root(&engine.dump_alloc()));
+    }
+
+    #[test]
+    fn proof() {
+        let mut engine = Engine::new();
+
+        let caller = address!("00000000000000000000000000000000000000cc");
+        let contract = address!("00000000000000000000000000000000000000aa");
+        let alloc: Alloc = serde_json::from_value(serde_json::json!({
+            "0x00000000000000000000000000000000000000aa": {
+                // Stores 1 at slot 0
+                "code": "0x600160005500",
+                "nonce": "0x1",
+                "storage": { "0x01": "0x2a", "0x0100": "0x07" },
+            },
+            "0x00000000000000000000000000000000000000bb": { "balance": "0x5" },
+            "0x00000000000000000000000000000000000000cc": {
+                "balance": "1000000000000000000",
+            },
+            // Empty (though existing) account
+            "0x00000000000000000000000000000000000000ee": {},
+        }))
+        .unwrap();
+        engine.load_alloc(alloc);
+
+        // Proofs are of the post-state (i.e. once committed)
+        engine
+            .execute_and_commit(TxEnv {
+                caller,
+                kind: TxKind::Call(contract),
+                ..Default::default()
+            })
+            .unwrap();
+        let state_root = engine.state_root();
+
+        let proof = engine
+            .proof(contract, &[U256::ZERO, U256::from(1), U256::from(2)])
+            .unwrap();
+        assert_eq!(proof.nonce, 1);
+        assert_eq!(proof.storage_hash, engine.storage_root(contract).unwrap());
+        assert_eq!(
+            proof
+                .storage_proof
+                .iter()
+                .map(|storage| storage.value)
+                .collect::<Vec<_>>(),
+            [U256::from(1), U256::from(0x2a), U256::ZERO]
+        );
+        assert_eq!(verify(state_root, &proof), Ok(()));
+
+        // Absent accounts (and zero-valued slots) are proven as such
+        let absent = engine
+            .proof(
+                address!("00000000000000000000000000000000000000dd"),
+                &[U256::ZERO],
+            )
+            .unwrap();
+        assert_eq!(absent.storage_hash, EMPTY_ROOT_HASH);
+        assert_eq!(absent.storage_proof[0].proof, Vec::<Bytes>::new());
+        assert_eq!(verify(state_root, &absent), Ok(()));
+        assert_eq!(engine.state_root(), state_root);
+
+        // Existing empty accounts are proven present (i.e. unlike absent ones)
+        let empty = engine
+            .proof(address!("00000000000000000000000000000000000000ee"), &[])
+            .unwrap();
+        assert_eq!(
+            (empty.nonce, empty.balance, empty.code_hash),
+            (0, U256::ZERO, KECCAK_EMPTY)
+        );
+        assert_ne!(empty.account_proof, absent.account_proof);
+        assert_eq!(verify(state_root, &empty), Ok(()));
+
+        // Proofs of other values (or against another root) are rejected
+        let mut tampered = proof.clone();
+        tampered.balance += U256::from(1);
+        assert!(matches!(
+            verify(state_root, &tampered),
+            Err(ProofError::Account(_))
+        ));
+        let mut tampered = proof.clone();
+        tampered.storage_proof[1].value = U256::from(0x2b);
+        assert!(matches!(
+            verify(state_root, &tampered),
+            Err(ProofError::Storage { key, .. }) if key == B256::with_last_byte(1)
+        ));
+        assert!(verify(EMPTY_ROOT_HASH, &proof).is_err());
+
+        // Proofs are (de)serialized as per `eth_getProof`
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(json["nonce"], "0x1");
+        assert_eq!(json["storageProof"][1]["value"], "0x2a");
+        assert_eq!(
+            json["storageProof"][1]["key"],
+            "0x0000000000000000000000000000000000000000000000000000000000000001"
+        );
+        assert_eq!(serde_json::from_value::<AccountProof>(json).unwrap(), proof);
+    }
+}
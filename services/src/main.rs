@@ -1,47 +1,379 @@
-use engine::{Engine, Event};
+use engine::{
+    Engine, Event, TraceConfig,
+    alloc::Alloc,
+    block::{Receipt, Withdrawal},
+    eip3155,
+    fork::{ForkDb, ForkError},
+    raw::{self, DecodeError},
+};
 use revm::{
+    DatabaseRef,
     bytecode::Bytecode,
-    context::{TxEnv, result::ResultAndState},
-    primitives::{Address, Bytes, TxKind, U256, address},
-    state::{AccountInfo, EvmStorage},
+    context::{
+        BlockEnv, TxEnv,
+        result::{self, ExecutionResult, ResultAndState},
+    },
+    context_interface::transaction::{AccessList, SignedAuthorization},
+    database::EmptyDBTyped,
+    primitives::{
+        Address, B256, Bytes, TxKind, U256, address, alloy_primitives::Bloom, hardfork::SpecId,
+    },
+    state::{AccountInfo, EvmState, EvmStorage},
 };
 use rocket::{
+    State,
+    fairing::AdHoc,
+    figment::Figment,
     fs::{FileServer, Options},
     serde::json::Json,
 };
 use rocket_okapi::{rapidoc::*, settings::UrlObject, swagger_ui::*};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc, time::Instant};
+
+/// Configuration of the (optional) fork of the service, e.g. in `Rocket.toml`:
+///
+/// ```toml
+/// [default.fork]
+/// url = "http://localhost:8545"  # JSON-RPC endpoint (omit to replay the cache offline)
+/// block = 19000000               # pinned block (defaults to the latest block)
+/// cache = "fork.json"            # on-disk cache of the fetched state
+/// ```
+#[derive(Debug, Deserialize)]
+struct ForkConfig {
+    url: Option<String>,
+    block: Option<u64>,
+    cache: Option<PathBuf>,
+}
+
+/// Backend database of the engine - i.e. empty, or state forked from a JSON-RPC endpoint
+#[derive(Clone)]
+enum Backend {
+    Empty(EmptyDBTyped<ForkError>),
+    Fork(Arc<ForkDb>),
+}
+
+impl Backend {
+    fn from_figment(figment: &Figment) -> Result<Self, String> {
+        if !figment.contains("fork") {
+            return Ok(Self::Empty(EmptyDBTyped::new()));
+        }
+
+        let config: ForkConfig = figment
+            .extract_inner("fork")
+            .map_err(|err| err.to_string())?;
+
+        let db = match config {
+            ForkConfig {
+                url: Some(url),
+                block,
+                cache,
+            } => {
+                let block = match block {
+                    Some(block) => block,
+                    None => ForkDb::latest_block(&url).map_err(|err| err.to_string())?,
+                };
+                let db = ForkDb::new(url, block);
+                match cache {
+                    Some(cache) => db.with_cache(cache).map_err(|err| err.to_string())?,
+                    None => db,
+                }
+            }
+            ForkConfig {
+                url: None,
+                cache: Some(cache),
+                ..
+            } => ForkDb::offline(cache).map_err(|err| err.to_string())?,
+            _ => return Err("fork requires a `url` (or `cache` to replay offline)".into()),
+        };
+
+        Ok(Self::Fork(Arc::new(db)))
+    }
+
+    fn block_env(&self) -> Result<BlockEnv, String> {
+        match self {
+            Self::Empty(_) => Ok(BlockEnv::default()),
+            Self::Fork(db) => db.block_env().map_err(|err| err.to_string()),
+        }
+    }
+
+    fn flush(&self) -> Result<(), String> {
+        match self {
+            Self::Empty(_) => Ok(()),
+            Self::Fork(db) => db.flush().map_err(|err| err.to_string()),
+        }
+    }
+
+    /// Runs an execution of the engine against the backend
+    ///
+    /// NOTE: a fork fetches state with blocking JSON-RPC requests, so its executions are run on a
+    /// blocking thread (i.e. rather than stalling the async workers of the service)
+    async fn run<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(Backend) -> Result<T, String> + Send + 'static,
+    ) -> Result<T, String> {
+        match self {
+            Self::Empty(_) => f(self.clone()),
+            Self::Fork(_) => {
+                let backend = self.clone();
+                rocket::tokio::task::spawn_blocking(move || f(backend))
+                    .await
+                    .map_err(|err| err.to_string())?
+            }
+        }
+    }
+}
+
+impl DatabaseRef for Backend {
+    type Error = ForkError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        match self {
+            Self::Empty(db) => db.basic_ref(address),
+            Self::Fork(db) => db.basic_ref(address),
+        }
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match self {
+            Self::Empty(db) => db.code_by_hash_ref(code_hash),
+            Self::Fork(db) => db.code_by_hash_ref(code_hash),
+        }
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        match self {
+            Self::Empty(db) => db.storage_ref(address, index),
+            Self::Fork(db) => db.storage_ref(address, index),
+        }
+    }
 
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        match self {
+            Self::Empty(db) => db.block_hash_ref(number),
+            Self::Fork(db) => db.block_hash_ref(number),
+        }
+    }
+}
+
+/// The contract deployed by a contract-creation transaction
 #[derive(Debug, serde::Serialize)]
-struct Response {
+struct Created {
+    address: Address,
+    /// Runtime code of the contract (i.e. as returned by its init code)
+    code: Bytes,
+}
+
+impl Created {
+    /// The contract deployed by a (successful) contract-creation transaction
+    fn from_result(result: &ExecutionResult) -> Option<Self> {
+        match result {
+            ExecutionResult::Success {
+                output: result::Output::Create(code, Some(address)),
+                ..
+            } => Some(Created {
+                address: *address,
+                code: code.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The trace (i.e. events) and summary of a transaction
+#[derive(Debug, serde::Serialize)]
+struct Trace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<Created>,
     events: Vec<Event>,
-    // TODO(toms): refine response object in line with <https://eips.ethereum.org/EIPS/eip-3155>
+    // NOTE: see `Format::Eip3155` for a summary in line with <https://eips.ethereum.org/EIPS/eip-3155>
     summary: ResultAndState,
 }
 
-#[rocket::post("/api/isolate/eval/<code>")]
-fn eval(code: &str) -> Result<Json<Response>, String> {
-    let mut engine = Engine::new();
+impl Trace {
+    fn new(summary: ResultAndState, events: Vec<Event>) -> Self {
+        Self {
+            created: Created::from_result(&summary.result),
+            events,
+            summary,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    fork: String,
+    /// Root of the state trie after the transaction - see [`Engine::state_root`]
+    state_root: B256,
+    #[serde(flatten)]
+    trace: Trace,
+}
 
-    let addr = address!("ffffffffffffffffffffffffffffffffffffffff");
+/// Output format of the trace
+#[derive(Debug, Default, rocket::FromFormField)]
+enum Format {
+    /// Events and summary (i.e. result and state) as a JSON object
+    #[default]
+    Json,
+    /// Steps and summary as JSON lines - see <https://eips.ethereum.org/EIPS/eip-3155>
+    Eip3155,
+}
 
-    engine.create_account(
-        addr,
-        AccountInfo::from_bytecode(Bytecode::new_raw(
-            Bytes::from_str(code).map_err(|err| err.to_string())?,
-        )),
-    );
+#[derive(Debug, rocket::Responder)]
+enum Output {
+    Json(Json<Response>),
+    #[response(content_type = "application/x-ndjson")]
+    Eip3155(String),
+}
 
+fn execute(
+    mut engine: Engine<Backend>,
+    tx: TxEnv,
+    format: Option<Format>,
+) -> Result<Output, String> {
+    let start = Instant::now();
+    // NOTE: the transaction is committed (i.e. to the discarded engine) for its state root
     let (summary, events) = engine
-        .execute(TxEnv {
-            kind: TxKind::Call(addr),
-            gas_limit: 0x1000000,
-            ..Default::default()
-        })
+        .execute_and_commit(tx)
         .map_err(|err| err.to_string())?;
+    let time = start.elapsed();
+
+    engine.db().db.flush()?;
+    let state_root = engine.state_root();
 
-    Ok(Json(Response { events, summary }))
+    Ok(match format.unwrap_or_default() {
+        Format::Json => Output::Json(Json(Response {
+            fork: engine.spec().to_string(),
+            state_root,
+            trace: Trace::new(summary, events),
+        })),
+        Format::Eip3155 => {
+            let mut output = Vec::new();
+            eip3155::write(
+                &mut output,
+                &events,
+                &eip3155::Summary::new(&summary.result, engine.spec())
+                    .with_state_root(state_root)
+                    .with_time(time),
+            )
+            .map_err(|err| err.to_string())?;
+            Output::Eip3155(String::from_utf8(output).map_err(|err| err.to_string())?)
+        }
+    })
+}
+
+#[derive(Debug, rocket::FromForm)]
+struct TraceOptions {
+    #[field(name = "disableStack")]
+    disable_stack: bool,
+    #[field(name = "disableMemory")]
+    disable_memory: bool,
+    #[field(name = "enableReturnData")]
+    enable_return_data: bool,
+    #[field(name = "enableStorage")]
+    enable_storage: bool,
+    #[field(name = "enableStackInputs")]
+    enable_stack_inputs: bool,
+    limit: Option<usize>,
+}
+
+fn engine(
+    backend: &Backend,
+    fork: Option<&str>,
+    config: TraceConfig,
+) -> Result<Engine<Backend>, String> {
+    // NOTE: transactions are simulated with a zero gas price (i.e. as per `eth_call`)
+    let mut engine = Engine::new()
+        .with_trace_config(config)
+        .with_base_fee_check(false)
+        .with_block(backend.block_env()?)
+        .with_db(backend.clone());
+    if let Some(fork) = fork {
+        engine = engine.with_spec(spec(fork)?);
+    }
+    Ok(engine)
+}
+
+/// The hardforks which can be executed against, as named in responses (i.e. by revm)
+const FORKS: [(&str, SpecId); 20] = [
+    ("Frontier", SpecId::FRONTIER),
+    ("Frontier Thawing", SpecId::FRONTIER_THAWING),
+    ("Homestead", SpecId::HOMESTEAD),
+    ("DAO Fork", SpecId::DAO_FORK),
+    ("Tangerine", SpecId::TANGERINE),
+    ("Spurious", SpecId::SPURIOUS_DRAGON),
+    ("Byzantium", SpecId::BYZANTIUM),
+    ("Constantinople", SpecId::CONSTANTINOPLE),
+    ("Petersburg", SpecId::PETERSBURG),
+    ("Istanbul", SpecId::ISTANBUL),
+    ("MuirGlacier", SpecId::MUIR_GLACIER),
+    ("Berlin", SpecId::BERLIN),
+    ("London", SpecId::LONDON),
+    ("Arrow Glacier", SpecId::ARROW_GLACIER),
+    ("Gray Glacier", SpecId::GRAY_GLACIER),
+    ("Merge", SpecId::MERGE),
+    ("Shanghai", SpecId::SHANGHAI),
+    ("Cancun", SpecId::CANCUN),
+    ("Prague", SpecId::PRAGUE),
+    // NOTE: revm's `OSAKA` is the EOF fork (i.e. not Osaka as since specified)
+    ("PragueEOF", SpecId::OSAKA),
+];
+
+/// The hardfork of a fork name (case-insensitively) - see [`FORKS`]
+fn spec(fork: &str) -> Result<SpecId, String> {
+    FORKS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(fork))
+        .map(|(_, spec)| *spec)
+        .ok_or_else(|| format!("unknown fork: {fork}"))
+}
+
+impl From<TraceOptions> for TraceConfig {
+    fn from(options: TraceOptions) -> Self {
+        TraceConfig {
+            disable_stack: options.disable_stack,
+            disable_memory: options.disable_memory,
+            enable_return_data: options.enable_return_data,
+            enable_storage: options.enable_storage,
+            enable_stack_inputs: options.enable_stack_inputs,
+            limit: options.limit,
+        }
+    }
+}
+
+#[rocket::post("/api/isolate/eval/<code>?<fork>&<format>&<options..>")]
+async fn eval(
+    backend: &State<Backend>,
+    code: String,
+    fork: Option<String>,
+    format: Option<Format>,
+    options: TraceOptions,
+) -> Result<Output, String> {
+    backend
+        .run(move |backend| {
+            let mut engine = engine(&backend, fork.as_deref(), options.into())?;
+
+            let addr = address!("ffffffffffffffffffffffffffffffffffffffff");
+
+            engine.create_account(
+                addr,
+                AccountInfo::from_bytecode(Bytecode::new_raw(
+                    Bytes::from_str(&code).map_err(|err| err.to_string())?,
+                )),
+            );
+
+            execute(
+                engine,
+                TxEnv {
+                    kind: TxKind::Call(addr),
+                    gas_limit: 0x1000000,
+                    ..Default::default()
+                },
+                format,
+            )
+        })
+        .await
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -54,64 +386,629 @@ struct Account {
     storage: EvmStorage,
 }
 
+/// Parameters of a transaction (each optional) - i.e. the sender, value, gas and fees, as well as
+/// the fields of typed transactions (see EIP-2930, EIP-1559, EIP-4844 and EIP-7702)
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Parameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    caller: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_price: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_priority_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_list: Option<AccessList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blob_versioned_hashes: Option<Vec<B256>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_fee_per_blob_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authorization_list: Option<Vec<SignedAuthorization>>,
+}
+
+impl Parameters {
+    /// Maps the parameters onto a transaction (whose type is derived from the fields provided),
+    /// defaulting the nonce to that of the caller (i.e. `nonce`)
+    ///
+    /// NOTE: as per geth, a gas price cannot be provided along with dynamic fees, and a maximum fee
+    /// alone implies a (dynamic fee) transaction without a priority fee.
+    fn apply(
+        self,
+        tx: TxEnv,
+        nonce: impl FnOnce(Address) -> Result<u64, String>,
+    ) -> Result<TxEnv, String> {
+        if self.gas_price.is_some()
+            && (self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some())
+        {
+            return Err(
+                "invalid transaction: both gasPrice and maxFeePerGas/maxPriorityFeePerGas provided"
+                    .to_string(),
+            );
+        }
+        let caller = self.caller.unwrap_or(tx.caller);
+        let nonce = match self.nonce {
+            Some(nonce) => nonce,
+            None => nonce(caller)?,
+        };
+
+        let mut tx = TxEnv {
+            caller,
+            value: self.value.unwrap_or(tx.value),
+            gas_limit: self.gas_limit.unwrap_or(tx.gas_limit),
+            gas_price: self
+                .gas_price
+                .or(self.max_fee_per_gas)
+                .map_or(tx.gas_price, |price| price.saturating_to()),
+            gas_priority_fee: self
+                .max_priority_fee_per_gas
+                .or(self.max_fee_per_gas.map(|_| U256::ZERO))
+                .map(|fee| fee.saturating_to()),
+            nonce,
+            access_list: self.access_list.unwrap_or_default(),
+            blob_hashes: self.blob_versioned_hashes.unwrap_or_default(),
+            max_fee_per_blob_gas: self
+                .max_fee_per_blob_gas
+                .unwrap_or_default()
+                .saturating_to(),
+            authorization_list: self.authorization_list.unwrap_or_default(),
+            ..tx
+        };
+        tx.derive_tx_type()
+            .map_err(|err| format!("invalid transaction: {err:?}"))?;
+        Ok(tx)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 enum Transaction {
     Call {
         address: Address,
         data: Option<Bytes>,
+        #[serde(flatten)]
+        parameters: Parameters,
+    },
+    Create {
+        init_code: Bytes,
+        #[serde(flatten)]
+        parameters: Parameters,
     },
 }
 
+impl Transaction {
+    /// Maps the transaction onto that executed by the engine, defaulting the nonce to that of the
+    /// caller (i.e. `nonce`)
+    fn tx_env(
+        self,
+        gas_limit: u64,
+        nonce: impl FnOnce(Address) -> Result<u64, String>,
+    ) -> Result<TxEnv, String> {
+        match self {
+            Transaction::Call {
+                address,
+                data,
+                parameters,
+            } => parameters.apply(
+                TxEnv {
+                    kind: TxKind::Call(address),
+                    data: data.unwrap_or_default(),
+                    gas_limit,
+                    ..Default::default()
+                },
+                nonce,
+            ),
+            Transaction::Create {
+                init_code,
+                parameters,
+            } => parameters.apply(
+                TxEnv {
+                    kind: TxKind::Create,
+                    data: init_code,
+                    gas_limit,
+                    ..Default::default()
+                },
+                nonce,
+            ),
+        }
+    }
+}
+
+/// The current nonce of an account (i.e. zero if it does not exist)
+fn nonce(engine: &Engine<Backend>, address: Address) -> Result<u64, String> {
+    Ok(engine
+        .db()
+        .basic_ref(address)
+        .map_err(|err| err.to_string())?
+        .map(|account| account.nonce)
+        .unwrap_or_default())
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Block {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coinbase: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    basefee: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prevrandao: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_limit: Option<u64>,
+}
+
+impl Block {
+    /// Overrides the (provided) fields of the block environment
+    fn apply(self, block: BlockEnv) -> BlockEnv {
+        BlockEnv {
+            number: self.number.unwrap_or(block.number),
+            timestamp: self.timestamp.unwrap_or(block.timestamp),
+            beneficiary: self.coinbase.unwrap_or(block.beneficiary),
+            basefee: self.basefee.unwrap_or(block.basefee),
+            prevrandao: self.prevrandao.or(block.prevrandao),
+            gas_limit: self.gas_limit.unwrap_or(block.gas_limit),
+            ..block
+        }
+    }
+}
+
+/// The (optional) block environment and state a transaction is executed in
 #[derive(Debug, Serialize, Deserialize)]
-struct Environment {
+struct Prestate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    block: Option<Block>,
+    /// Prestate (i.e. genesis `alloc`), created before the `accounts`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alloc: Option<Alloc>,
+    #[serde(default)]
     accounts: Box<[Account]>,
-    transaction: Transaction,
 }
 
-#[rocket::post("/api/isolate/transaction", data = "<environment>")]
-fn transaction(environment: Json<Environment>) -> Result<Json<Response>, String> {
-    let environment = environment.into_inner();
+impl Prestate {
+    /// Seeds the engine with the state and block
+    fn apply(self, backend: &Backend, engine: Engine<Backend>) -> Result<Engine<Backend>, String> {
+        let block = self.block.unwrap_or_default().apply(backend.block_env()?);
+        let mut engine = engine.with_block(block);
 
-    let mut engine = Engine::new();
+        if let Some(alloc) = self.alloc {
+            engine.load_alloc(alloc);
+        }
 
-    for Account {
-        address,
-        balance,
-        nonce,
-        code,
-        storage,
-    } in environment.accounts
-    {
-        engine.create_account(
+        for Account {
             address,
-            revm::state::Account::from(match code {
-                None => AccountInfo::from_balance(balance).with_nonce(nonce),
-                Some(code) => AccountInfo::from_bytecode(Bytecode::new_raw(code)),
-            })
-            .with_storage(storage.into_iter()),
-        );
+            balance,
+            nonce,
+            code,
+            storage,
+        } in self.accounts
+        {
+            engine.create_account(
+                address,
+                revm::state::Account::from(match code {
+                    None => AccountInfo::from_balance(balance).with_nonce(nonce),
+                    Some(code) => AccountInfo::from_bytecode(Bytecode::new_raw(code)),
+                })
+                .with_storage(storage.into_iter()),
+            );
+        }
+
+        Ok(engine)
     }
+}
 
-    let (summary, events) = engine
-        .execute(match environment.transaction {
-            Transaction::Call { address, data } => TxEnv {
-                kind: TxKind::Call(address),
-                data: data.unwrap_or_default(),
-                gas_limit: 0x1000000,
-                ..Default::default()
-            },
+#[derive(Debug, Serialize, Deserialize)]
+struct Environment {
+    #[serde(flatten)]
+    prestate: Prestate,
+    transaction: Transaction,
+}
+
+impl Environment {
+    /// Seeds the engine with the environment's state and block, returning the transaction
+    fn prepare(
+        self,
+        backend: &Backend,
+        engine: Engine<Backend>,
+    ) -> Result<(Engine<Backend>, TxEnv), String> {
+        let engine = self.prestate.apply(backend, engine)?;
+        // NOTE: the transaction cannot exceed the gas limit of the block
+        let gas_limit = engine.block().gas_limit.min(0x1000000);
+
+        let tx = self
+            .transaction
+            .tx_env(gas_limit, |caller| nonce(&engine, caller))?;
+
+        Ok((engine, tx))
+    }
+}
+
+#[rocket::post(
+    "/api/isolate/transaction?<fork>&<format>&<options..>",
+    data = "<environment>"
+)]
+async fn transaction(
+    backend: &State<Backend>,
+    environment: Json<Environment>,
+    fork: Option<String>,
+    format: Option<Format>,
+    options: TraceOptions,
+) -> Result<Output, String> {
+    backend
+        .run(move |backend| {
+            let (engine, tx) = environment
+                .into_inner()
+                .prepare(&backend, engine(&backend, fork.as_deref(), options.into())?)?;
+
+            execute(engine, tx, format)
         })
-        .map_err(|err| err.to_string())?;
+        .await
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EstimateResponse {
+    fork: String,
+    /// Minimal gas limit with which the transaction succeeds
+    gas: u64,
+    #[serde(flatten)]
+    trace: Trace,
+}
+
+#[rocket::post("/api/isolate/estimate-gas?<fork>&<options..>", data = "<environment>")]
+async fn estimate_gas(
+    backend: &State<Backend>,
+    environment: Json<Environment>,
+    fork: Option<String>,
+    options: TraceOptions,
+) -> Result<Json<EstimateResponse>, String> {
+    backend
+        .run(move |backend| {
+            let (mut engine, tx) = environment
+                .into_inner()
+                .prepare(&backend, engine(&backend, fork.as_deref(), options.into())?)?;
+
+            // NOTE: the gas limit of the transaction (i.e. up to that of the block) bounds the
+            // search
+            let estimate = engine.estimate_gas(tx).map_err(|err| err.to_string())?;
+            engine.db().db.flush()?;
+
+            Ok(Json(EstimateResponse {
+                fork: engine.spec().to_string(),
+                gas: estimate.gas_limit,
+                trace: Trace::new(estimate.result, estimate.events),
+            }))
+        })
+        .await
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AccessListResponse {
+    fork: String,
+    access_list: AccessList,
+    /// Gas used by the transaction with the access list
+    gas_used: u64,
+    /// Gas saved by the access list (i.e. negative if it costs more than it saves)
+    gas_saved: i64,
+    #[serde(flatten)]
+    trace: Trace,
+}
+
+#[rocket::post("/api/isolate/access-list?<fork>&<options..>", data = "<environment>")]
+async fn access_list(
+    backend: &State<Backend>,
+    environment: Json<Environment>,
+    fork: Option<String>,
+    options: TraceOptions,
+) -> Result<Json<AccessListResponse>, String> {
+    backend
+        .run(move |backend| {
+            let (mut engine, tx) = environment
+                .into_inner()
+                .prepare(&backend, engine(&backend, fork.as_deref(), options.into())?)?;
+
+            let created = engine
+                .create_access_list(tx)
+                .map_err(|err| err.to_string())?;
+            engine.db().db.flush()?;
+
+            Ok(Json(AccessListResponse {
+                fork: engine.spec().to_string(),
+                access_list: created.access_list,
+                gas_used: created.gas_used,
+                gas_saved: created.gas_saved,
+                trace: Trace::new(created.result, created.events),
+            }))
+        })
+        .await
+}
+
+/// A signed raw transaction (i.e. legacy or EIP-2718 typed), and the environment it is executed in
+#[derive(Debug, Serialize, Deserialize)]
+struct RawEnvironment {
+    #[serde(flatten)]
+    prestate: Prestate,
+    raw: Bytes,
+}
+
+/// A raw transaction which could not be decoded (or whose sender could not be recovered)
+#[derive(Debug, Serialize)]
+struct DecodeFailure {
+    kind: &'static str,
+    message: String,
+}
+
+/// Error of a raw transaction - i.e. a `400 Bad Request` (with a [`DecodeFailure`]) if invalid
+#[derive(Debug, rocket::Responder)]
+enum RawError {
+    #[response(status = 400)]
+    Decode(Json<DecodeFailure>),
+    Other(String),
+}
+
+impl From<DecodeError> for RawError {
+    fn from(err: DecodeError) -> Self {
+        let kind = match err {
+            DecodeError::Rlp(_) => "rlp",
+            DecodeError::UnsupportedType(_) => "unsupportedType",
+            DecodeError::Signature(_) => "signature",
+        };
+        Self::Decode(Json(DecodeFailure {
+            kind,
+            message: err.to_string(),
+        }))
+    }
+}
+
+impl From<String> for RawError {
+    fn from(err: String) -> Self {
+        Self::Other(err)
+    }
+}
+
+#[rocket::post(
+    "/api/isolate/raw-transaction?<fork>&<format>&<options..>",
+    data = "<environment>"
+)]
+async fn raw_transaction(
+    backend: &State<Backend>,
+    environment: Json<RawEnvironment>,
+    fork: Option<String>,
+    format: Option<Format>,
+    options: TraceOptions,
+) -> Result<Output, RawError> {
+    let RawEnvironment { prestate, raw } = environment.into_inner();
+    let tx = raw::decode(&raw)?;
 
-    Ok(Json(Response { events, summary }))
+    Ok(backend
+        .run(move |backend| {
+            let mut engine =
+                prestate.apply(&backend, engine(&backend, fork.as_deref(), options.into())?)?;
+            // NOTE: the engine adopts the chain ID of the transaction, so those of any chain can
+            // be traced (i.e. not only mainnet)
+            if let Some(chain_id) = tx.chain_id {
+                engine = engine.with_chain_id(chain_id);
+            }
+
+            execute(engine, tx, format)
+        })
+        .await?)
+}
+
+/// Maps a sequence of transactions onto those executed by the engine
+fn tx_envs(engine: &Engine<Backend>, transactions: Vec<Transaction>) -> Result<Vec<TxEnv>, String> {
+    let gas_limit = engine.block().gas_limit.min(0x1000000);
+
+    // NOTE: nonces default to those of the callers as of their previous transaction (if any)
+    let mut nonces = HashMap::new();
+    transactions
+        .into_iter()
+        .map(|transaction| {
+            let tx = transaction.tx_env(gas_limit, |caller| match nonces.get(&caller) {
+                Some(nonce) => Ok(*nonce),
+                None => nonce(engine, caller),
+            })?;
+            nonces.insert(tx.caller, tx.nonce + 1);
+            Ok(tx)
+        })
+        .collect()
+}
+
+/// A bundle of transactions, and the environment they are executed in (in order)
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleEnvironment {
+    #[serde(flatten)]
+    prestate: Prestate,
+    transactions: Vec<Transaction>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleResponse {
+    fork: String,
+    transactions: Vec<Trace>,
+    /// Cumulative state changes of the transactions
+    state: EvmState,
+    /// Root of the state trie after the transactions
+    state_root: B256,
+}
+
+#[rocket::post("/api/isolate/bundle?<fork>&<options..>", data = "<environment>")]
+async fn bundle(
+    backend: &State<Backend>,
+    environment: Json<BundleEnvironment>,
+    fork: Option<String>,
+    options: TraceOptions,
+) -> Result<Json<BundleResponse>, String> {
+    let BundleEnvironment {
+        prestate,
+        transactions,
+    } = environment.into_inner();
+
+    backend
+        .run(move |backend| {
+            let mut engine =
+                prestate.apply(&backend, engine(&backend, fork.as_deref(), options.into())?)?;
+            let txs = tx_envs(&engine, transactions)?;
+
+            let bundle = engine.execute_bundle(txs).map_err(|err| err.to_string())?;
+            engine.db().db.flush()?;
+
+            Ok(Json(BundleResponse {
+                fork: engine.spec().to_string(),
+                transactions: bundle
+                    .transactions
+                    .into_iter()
+                    .map(|(summary, events)| Trace::new(summary, events))
+                    .collect(),
+                state: bundle.state,
+                state_root: engine.state_root(),
+            }))
+        })
+        .await
+}
+
+/// A block of transactions (followed by its reward and withdrawals), and the environment it is
+/// executed in
+#[derive(Debug, Serialize, Deserialize)]
+struct BlockEnvironment {
+    #[serde(flatten)]
+    prestate: Prestate,
+    #[serde(default)]
+    transactions: Vec<Transaction>,
+    #[serde(default)]
+    withdrawals: Vec<Withdrawal>,
+    /// Reward of the block's coinbase (defaults to that of the fork)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reward: Option<U256>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockResponse {
+    fork: String,
+    transactions: Vec<Trace>,
+    receipts: Vec<Receipt>,
+    gas_used: u64,
+    logs_bloom: Bloom,
+    receipts_root: B256,
+    state_root: B256,
+}
+
+#[rocket::post("/api/isolate/block?<fork>&<options..>", data = "<environment>")]
+async fn block(
+    backend: &State<Backend>,
+    environment: Json<BlockEnvironment>,
+    fork: Option<String>,
+    options: TraceOptions,
+) -> Result<Json<BlockResponse>, String> {
+    let BlockEnvironment {
+        prestate,
+        transactions,
+        withdrawals,
+        reward,
+    } = environment.into_inner();
+
+    backend
+        .run(move |backend| {
+            let mut engine =
+                prestate.apply(&backend, engine(&backend, fork.as_deref(), options.into())?)?;
+            let transactions = tx_envs(&engine, transactions)?;
+
+            let executed = engine
+                .execute_block(engine::block::Block {
+                    transactions,
+                    withdrawals,
+                    reward,
+                })
+                .map_err(|err| err.to_string())?;
+            engine.db().db.flush()?;
+
+            Ok(Json(BlockResponse {
+                fork: engine.spec().to_string(),
+                transactions: executed
+                    .transactions
+                    .into_iter()
+                    .map(|(summary, events)| Trace::new(summary, events))
+                    .collect(),
+                receipts: executed.receipts,
+                gas_used: executed.gas_used,
+                logs_bloom: executed.logs_bloom,
+                receipts_root: executed.receipts_root,
+                state_root: executed.state_root,
+            }))
+        })
+        .await
+}
+
+#[rocket::post("/api/isolate/post-state?<fork>", data = "<environment>")]
+async fn post_state(
+    backend: &State<Backend>,
+    environment: Json<Environment>,
+    fork: Option<String>,
+) -> Result<Json<Alloc>, String> {
+    backend
+        .run(move |backend| {
+            let (mut engine, tx) = environment.into_inner().prepare(
+                &backend,
+                engine(&backend, fork.as_deref(), TraceConfig::default())?,
+            )?;
+
+            engine
+                .execute_and_commit(tx)
+                .map_err(|err| err.to_string())?;
+            engine.db().db.flush()?;
+
+            Ok(Json(engine.dump_alloc()))
+        })
+        .await
 }
 
 #[rocket::launch]
 fn rocket() -> _ {
     rocket::build()
-        .mount("/", rocket::routes![eval, transaction])
+        .attach(AdHoc::try_on_ignite("Fork", |rocket| async {
+            // NOTE: setting up a fork fetches its (latest) block, i.e. a blocking request
+            let figment = rocket.figment().clone();
+            let backend =
+                rocket::tokio::task::spawn_blocking(move || Backend::from_figment(&figment))
+                    .await
+                    .map_err(|err| err.to_string())
+                    .and_then(|backend| backend);
+            match backend {
+                Ok(backend) => Ok(rocket.manage(backend)),
+                Err(err) => {
+                    rocket::error!("invalid fork: {err}");
+                    Err(rocket)
+                }
+            }
+        }))
+        .mount(
+            "/",
+            rocket::routes![
+                eval,
+                transaction,
+                raw_transaction,
+                estimate_gas,
+                access_list,
+                bundle,
+                block,
+                post_state
+            ],
+        )
         .mount("/res", FileServer::new("res", Options::default()))
         .mount(
             "/swagger-ui/",
@@ -136,3 +1033,158 @@ fn rocket() -> _ {
             }),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::context_interface::transaction::TransactionType;
+    use rocket::serde::json::{json, serde_json};
+
+    fn tx_env(transaction: serde_json::Value) -> Result<TxEnv, String> {
+        serde_json::from_value::<Transaction>(transaction)
+            .unwrap()
+            .tx_env(30_000_000, |_| Ok(7))
+    }
+
+    #[test]
+    fn tx_type() {
+        let call = |parameters: serde_json::Value| {
+            let mut transaction = json!({
+                "type": "call",
+                "address": "0x00000000000000000000000000000000000000aa",
+            });
+            transaction
+                .as_object_mut()
+                .unwrap()
+                .extend(parameters.as_object().unwrap().clone());
+            tx_env(transaction).unwrap()
+        };
+
+        let tx = call(json!({ "gasPrice": "0x2" }));
+        assert_eq!(tx.tx_type, TransactionType::Legacy as u8);
+        assert_eq!((tx.gas_price, tx.gas_priority_fee), (2, None));
+
+        let tx = call(json!({
+            "accessList": [{
+                "address": "0x00000000000000000000000000000000000000bb",
+                "storageKeys": [],
+            }],
+        }));
+        assert_eq!(tx.tx_type, TransactionType::Eip2930 as u8);
+
+        let tx = call(json!({ "maxFeePerGas": "0x3", "maxPriorityFeePerGas": "0x1" }));
+        assert_eq!(tx.tx_type, TransactionType::Eip1559 as u8);
+        assert_eq!((tx.gas_price, tx.gas_priority_fee), (3, Some(1)));
+
+        // A maximum fee alone implies no priority fee
+        let tx = call(json!({ "maxFeePerGas": "0x3" }));
+        assert_eq!(tx.tx_type, TransactionType::Eip1559 as u8);
+        assert_eq!((tx.gas_price, tx.gas_priority_fee), (3, Some(0)));
+
+        let tx = call(json!({
+            "maxFeePerGas": "0x3",
+            "maxPriorityFeePerGas": "0x1",
+            "blobVersionedHashes": [
+                "0x0100000000000000000000000000000000000000000000000000000000000000",
+            ],
+            "maxFeePerBlobGas": "0x4",
+        }));
+        assert_eq!(tx.tx_type, TransactionType::Eip4844 as u8);
+        assert_eq!(tx.max_fee_per_blob_gas, 4);
+
+        let tx = call(json!({
+            "authorizationList": [{
+                "chainId": "0x1",
+                "address": "0x00000000000000000000000000000000000000bb",
+                "nonce": "0x0",
+                "yParity": "0x0",
+                "r": "0x1",
+                "s": "0x1",
+            }],
+        }));
+        assert_eq!(tx.tx_type, TransactionType::Eip7702 as u8);
+    }
+
+    #[test]
+    fn parameters() {
+        let tx = tx_env(json!({ "type": "create", "initCode": "0x00" })).unwrap();
+        assert_eq!(tx.kind, TxKind::Create);
+        assert_eq!(tx.data, Bytes::from_static(&[0x00]));
+        assert_eq!(tx.gas_limit, 30_000_000);
+
+        // The nonce defaults to that of the caller (unless provided)
+        assert_eq!(tx.nonce, 7);
+        let tx = tx_env(json!({
+            "type": "call",
+            "address": "0x00000000000000000000000000000000000000aa",
+            "caller": "0x00000000000000000000000000000000000000cc",
+            "nonce": 1,
+            "gasLimit": 21000,
+        }))
+        .unwrap();
+        assert_eq!(
+            (tx.caller, tx.nonce, tx.gas_limit),
+            (
+                address!("00000000000000000000000000000000000000cc"),
+                1,
+                21_000
+            )
+        );
+
+        let failed = Parameters::default().apply(TxEnv::default(), |_| Err("failed".to_string()));
+        assert_eq!(failed.unwrap_err(), "failed");
+    }
+
+    #[test]
+    fn forks() {
+        // Fork names are those of the responses, matched case-insensitively
+        for (name, spec) in FORKS {
+            assert_eq!(spec.to_string(), name);
+        }
+        assert_eq!(super::spec("Cancun"), Ok(SpecId::CANCUN));
+        assert_eq!(super::spec("cancun"), Ok(SpecId::CANCUN));
+        assert_eq!(super::spec("arrow glacier"), Ok(SpecId::ARROW_GLACIER));
+        assert_eq!(super::spec("pragueeof"), Ok(SpecId::OSAKA));
+        assert_eq!(super::spec("Osaka"), Err("unknown fork: Osaka".to_string()));
+    }
+
+    #[test]
+    fn mismatched() {
+        // A gas price cannot be provided along with dynamic fees
+        for fee in ["maxFeePerGas", "maxPriorityFeePerGas"] {
+            let err = tx_env(json!({
+                "type": "call",
+                "address": "0x00000000000000000000000000000000000000aa",
+                "gasPrice": "0x2",
+                fee: "0x1",
+            }))
+            .unwrap_err();
+            assert!(err.contains("gasPrice"), "{err}");
+        }
+
+        // Blob and set code transactions cannot create contracts
+        let err = tx_env(json!({
+            "type": "create",
+            "initCode": "0x00",
+            "blobVersionedHashes": [
+                "0x0100000000000000000000000000000000000000000000000000000000000000",
+            ],
+        }))
+        .unwrap_err();
+        assert!(err.contains("Eip4844"), "{err}");
+        let err = tx_env(json!({
+            "type": "create",
+            "initCode": "0x00",
+            "authorizationList": [{
+                "chainId": "0x1",
+                "address": "0x00000000000000000000000000000000000000bb",
+                "nonce": "0x0",
+                "yParity": "0x0",
+                "r": "0x1",
+                "s": "0x1",
+            }],
+        }))
+        .unwrap_err();
+        assert!(err.contains("Eip7702"), "{err}");
+    }
+}